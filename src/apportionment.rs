@@ -0,0 +1,149 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dhondt::{DHondtError, Quotient};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum Method {
+    /// D'Hondt (divisors 1, 2, 3...)
+    #[default]
+    Dhondt,
+    /// Hare-Niemeyer (largest remainders of the Hare quota)
+    HareNiemeyer,
+    /// Sainte-Laguë/Schepers (divisors 1, 3, 5...)
+    SainteLague,
+}
+
+impl Method {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Method::Dhondt => "D'Hondt",
+            Method::HareNiemeyer => "Hare-Niemeyer",
+            Method::SainteLague => "Sainte-Laguë/Schepers",
+        }
+    }
+
+    /// Quotient a party with `seats` seats competes with for the next one,
+    /// for highest-averages methods.
+    pub fn quotient(&self, votes: u32, seats: u32) -> Option<Quotient> {
+        Some(Quotient::new(votes, self.divisor(seats)?))
+    }
+
+    /// Divisor of that quotient.
+    pub fn divisor(&self, seats: u32) -> Option<u32> {
+        match self {
+            Method::Dhondt => Some(seats + 1),
+            Method::SainteLague => Some(2 * seats + 1),
+            Method::HareNiemeyer => None,
+        }
+    }
+}
+
+/// Distributes `seat_count` seats proportionally to the given weights (votes
+/// or group sizes). The returned seat counts follow the order of `weights`.
+pub fn apportion(
+    method: Method,
+    seat_count: u32,
+    weights: &[(String, u32)],
+) -> Result<Vec<u32>, DHondtError> {
+    let mut seats = vec![0; weights.len()];
+
+    for (i, _) in award_order(method, seat_count, weights)? {
+        seats[i] += 1;
+    }
+
+    Ok(seats)
+}
+
+/// Awards seats one at a time, returning the index (in `weights`) of the
+/// winner of every seat and, for highest-averages methods, the quotient it
+/// was won with. With largest remainders, seats won with full quotas come
+/// before those won with remainders.
+pub fn award_order(
+    method: Method,
+    seat_count: u32,
+    weights: &[(String, u32)],
+) -> Result<Vec<(usize, Option<Quotient>)>, DHondtError> {
+    if seat_count == 0 {
+        return Err(DHondtError::ZeroSeats);
+    }
+
+    if weights.is_empty() {
+        return Err(DHondtError::NoParties);
+    }
+
+    if weights.iter().all(|w| w.1 == 0) {
+        return Err(DHondtError::NoVotes);
+    }
+
+    Ok(match method {
+        Method::Dhondt | Method::SainteLague => highest_averages(method, seat_count, weights),
+        Method::HareNiemeyer => largest_remainders(seat_count, weights),
+    })
+}
+
+fn highest_averages(
+    method: Method,
+    seat_count: u32,
+    weights: &[(String, u32)],
+) -> Vec<(usize, Option<Quotient>)> {
+    let mut seats = vec![0; weights.len()];
+    let mut order = Vec::with_capacity(seat_count as usize);
+
+    // The next quotient of every party. Ties go to the larger weight, then to
+    // the one listed first
+    let mut next: BinaryHeap<(Quotient, u32, Reverse<usize>)> = weights
+        .iter()
+        .enumerate()
+        .filter_map(|(i, w)| Some((method.quotient(w.1, 0)?, w.1, Reverse(i))))
+        .collect();
+
+    for _ in 0..seat_count {
+        let (seat_quotient, weight, Reverse(i)) = next.pop().unwrap();
+
+        seats[i] += 1;
+        order.push((i, Some(seat_quotient)));
+
+        if let Some(quotient) = method.quotient(weight, seats[i]) {
+            next.push((quotient, weight, Reverse(i)));
+        }
+    }
+
+    order
+}
+
+fn largest_remainders(
+    seat_count: u32,
+    weights: &[(String, u32)],
+) -> Vec<(usize, Option<Quotient>)> {
+    let total: u64 = weights.iter().map(|w| w.1 as u64).sum();
+
+    let mut order: Vec<(usize, Option<Quotient>)> = weights
+        .iter()
+        .enumerate()
+        .flat_map(|(i, w)| {
+            let full_quotas = w.1 as u64 * seat_count as u64 / total;
+            std::iter::repeat_n((i, None), full_quotas as usize)
+        })
+        .collect();
+
+    let remainders: Vec<u64> = weights
+        .iter()
+        .map(|w| w.1 as u64 * seat_count as u64 % total)
+        .collect();
+
+    let mut by_remainder: Vec<usize> = (0..weights.len()).collect();
+    by_remainder.sort_by(|&a, &b| {
+        remainders[b]
+            .cmp(&remainders[a])
+            .then(weights[b].1.cmp(&weights[a].1))
+    });
+
+    let left = seat_count as usize - order.len();
+    order.extend(by_remainder.into_iter().take(left).map(|i| (i, None)));
+
+    order
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use crate::{election::AllocationResult, error::Error, i18n::t, text_table::format_table};
+
+/// Outcome of calculating one of the files of a batch.
+#[derive(Debug)]
+pub struct BatchFile {
+    pub path: String,
+    /// Results of every district of the file, or why they couldn't be
+    /// calculated
+    pub results: Result<Vec<(String, AllocationResult)>, Error>,
+    /// Problems found in the data that didn't stop the calculation (the
+    /// warnings of the results are added to these in the summary)
+    pub warnings: Vec<String>,
+}
+
+impl BatchFile {
+    /// Warnings of the data and of the results of every district.
+    pub fn all_warnings(&self) -> Vec<String> {
+        let mut warnings = self.warnings.clone();
+
+        if let Ok(ref results) = self.results {
+            for (district, result) in results {
+                warnings.extend(
+                    result
+                        .warnings
+                        .iter()
+                        .map(|warning| match district.as_str() {
+                            "" => warning.clone(),
+                            district => format!("{district}: {warning}"),
+                        }),
+                );
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Table of the seats won by every party in each file (added up over its
+/// districts), followed by the errors and warnings of every file.
+pub fn generate_summary(files: &[BatchFile]) -> String {
+    let mut output = String::new();
+
+    let mut seats_by_file = Vec::new();
+    let mut total_seats: HashMap<&str, u32> = HashMap::new();
+
+    for file in files {
+        let mut seats: HashMap<&str, u32> = HashMap::new();
+
+        if let Ok(ref results) = file.results {
+            for party in results.iter().flat_map(|(_, result)| &result.parties) {
+                *seats.entry(&party.name).or_default() += party.seats;
+                *total_seats.entry(&party.name).or_default() += party.seats;
+            }
+        }
+
+        seats_by_file.push(seats);
+    }
+
+    // Parties that won seats anywhere, the ones with the most first
+    let mut parties: Vec<(&str, u32)> = total_seats
+        .into_iter()
+        .filter(|&(_, seats)| seats > 0)
+        .collect();
+    parties.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let failed = files.iter().filter(|f| f.results.is_err()).count();
+    output.push_str(&format!(
+        "{} {}\n{} {}\n{} {failed}\n\n",
+        t("Files:"),
+        files.len(),
+        t("Calculated:"),
+        files.len() - failed,
+        t("Failed:")
+    ));
+
+    let mut lines = vec![std::iter::once(t("File"))
+        .chain(parties.iter().map(|&(name, _)| name))
+        .chain([t("Seats"), t("Warnings"), t("Status")])
+        .map(String::from)
+        .collect::<Vec<_>>()];
+
+    for (file, seats) in files.iter().zip(&seats_by_file) {
+        let mut line = vec![file.path.clone()];
+
+        match file.results {
+            Ok(ref results) => {
+                line.extend(
+                    parties
+                        .iter()
+                        .map(|&(name, _)| seats.get(name).map_or("0".into(), |s| s.to_string())),
+                );
+                line.push(
+                    results
+                        .iter()
+                        .map(|(_, r)| r.seat_count)
+                        .sum::<u32>()
+                        .to_string(),
+                );
+                line.push(file.all_warnings().len().to_string());
+                line.push("ok".into());
+            }
+            Err(ref err) => {
+                line.extend(parties.iter().map(|_| "-".to_string()));
+                line.push("-".into());
+                line.push(file.warnings.len().to_string());
+                line.push(err.code().into());
+            }
+        }
+
+        lines.push(line);
+    }
+
+    let mut left_align = vec![false; lines[0].len()];
+    left_align[0] = true;
+    *left_align.last_mut().unwrap() = true;
+
+    output.push_str(&format_table(&lines, &left_align));
+
+    if failed > 0 {
+        output.push_str(&format!("\n{}\n", t("Errors:")));
+
+        for file in files {
+            // Only allocation errors don't tell the file they come from
+            match file.results {
+                Err(ref err @ Error::Allocation(_)) => output.push_str(&format!(
+                    "error[{}]: {}: {}\n",
+                    err.code(),
+                    file.path,
+                    err.describe()
+                )),
+                Err(ref err) => {
+                    output.push_str(&format!("error[{}]: {}\n", err.code(), err.describe()))
+                }
+                Ok(_) => {}
+            }
+        }
+    }
+
+    let warnings: Vec<(&str, Vec<String>)> = files
+        .iter()
+        .map(|f| (f.path.as_str(), f.all_warnings()))
+        .filter(|(_, warnings)| !warnings.is_empty())
+        .collect();
+
+    if !warnings.is_empty() {
+        output.push_str("\nWarnings:\n");
+
+        for (path, warnings) in warnings {
+            for warning in warnings {
+                output.push_str(&format!("{path}: {warning}\n"));
+            }
+        }
+    }
+
+    output
+}
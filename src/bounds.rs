@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use crate::{
+    apportionment::Method,
+    dhondt::{self, reaches_threshold, DHondtError, Options},
+    i18n::t,
+    numeric::{format_decimal, format_num},
+    text_table::format_table,
+};
+
+pub struct SeatBounds {
+    pub party: String,
+    pub votes: u32,
+    pub seats: u32,
+    pub min: u32,
+    pub max: u32,
+}
+
+/// Number of quotients of a party with `votes` above `target_votes /
+/// target_divisor`, counting ties as above when `count_ties` is set, up to
+/// `limit`.
+fn quotients_above(
+    method: Method,
+    votes: u32,
+    target_votes: u32,
+    target_divisor: u32,
+    count_ties: bool,
+    limit: u64,
+) -> u64 {
+    let numerator = votes as u64 * target_divisor as u64;
+
+    (0..limit)
+        .take_while(|&seats| {
+            let divisor = method.divisor(seats as u32).unwrap_or(u32::MAX) as u64;
+            let target = target_votes as u64 * divisor;
+
+            numerator > target || (count_ties && numerator == target)
+        })
+        .count() as u64
+}
+
+/// Largest number of seats `party` can get if it receives every remaining
+/// vote and ties go its way.
+fn max_seats(
+    seat_count: u32,
+    party: &str,
+    votes_by_party: &HashMap<String, u32>,
+    remaining_votes: u32,
+    options: &Options,
+) -> u32 {
+    let total_votes = votes_by_party.values().sum::<u32>() as u64 + remaining_votes as u64;
+    let votes = votes_by_party[party].saturating_add(remaining_votes);
+    if votes == 0 || !reaches_threshold(votes, total_votes, options) {
+        return 0;
+    }
+
+    // Every other party keeps its votes, so only those already reaching
+    // the threshold compete
+    let rivals: Vec<u32> = votes_by_party
+        .iter()
+        .filter(|(p, &v)| p.as_str() != party && reaches_threshold(v, total_votes, options))
+        .map(|(_, &v)| v)
+        .collect();
+
+    (1..=seat_count)
+        .take_while(|&m| {
+            let divisor = options.method.divisor(m - 1).unwrap_or(u32::MAX);
+            let above: u64 = rivals
+                .iter()
+                .map(|&v| {
+                    quotients_above(options.method, v, votes, divisor, false, seat_count as u64)
+                })
+                .sum();
+
+            above <= (seat_count - m) as u64
+        })
+        .last()
+        .unwrap_or(0)
+}
+
+/// Smallest number of seats `party` can end up with if every remaining vote
+/// goes to its rivals in the worst possible way and ties go against it.
+fn min_seats(
+    seat_count: u32,
+    party: &str,
+    votes_by_party: &HashMap<String, u32>,
+    remaining_votes: u32,
+    options: &Options,
+) -> u32 {
+    let total_votes = votes_by_party.values().sum::<u32>() as u64 + remaining_votes as u64;
+    let votes = votes_by_party[party];
+    if votes == 0 || !reaches_threshold(votes, total_votes, options) {
+        return 0;
+    }
+
+    // Rivals that could reach the threshold with the remaining votes compete
+    // as if they already did (which only lowers the bound)
+    let rivals: Vec<u32> = votes_by_party
+        .iter()
+        .filter(|(p, &v)| {
+            p.as_str() != party
+                && reaches_threshold(v.saturating_add(remaining_votes), total_votes, options)
+        })
+        .map(|(_, &v)| v)
+        .collect();
+
+    (1..=seat_count)
+        .take_while(|&m| {
+            let method = options.method;
+            let divisor = method.divisor(m - 1).unwrap_or(u32::MAX);
+
+            // Rivals need this many quotients at or above the party's m-th
+            // quotient to keep it from getting m seats
+            let needed = (seat_count - m + 1) as u64;
+
+            let current: Vec<u64> = rivals
+                .iter()
+                .map(|&v| quotients_above(method, v, votes, divisor, true, needed))
+                .collect();
+            let free: u64 = current.iter().sum();
+
+            if free >= needed {
+                return false;
+            }
+
+            // Votes needed for every extra quotient (a lower bound, as the
+            // costs are sorted without keeping each party's order)
+            let mut costs: Vec<u64> = Vec::new();
+
+            for (&v, &current) in rivals.iter().zip(&current) {
+                let mut previous = v as u64;
+                for c in current..current + needed {
+                    let seat_divisor = method.divisor(c as u32).unwrap_or(u32::MAX) as u64;
+                    let required = (seat_divisor * votes as u64).div_ceil(divisor as u64);
+                    costs.push(required.saturating_sub(previous));
+                    previous = previous.max(required);
+                }
+            }
+
+            costs.sort_unstable();
+            let cost: u64 = costs.iter().take((needed - free) as usize).sum();
+
+            cost > remaining_votes as u64
+        })
+        .last()
+        .unwrap_or(0)
+}
+
+/// Computes, for every party, the guaranteed minimum and possible maximum
+/// seats given that up to `remaining_votes` votes are still to be counted.
+pub fn seat_bounds(
+    seat_count: u32,
+    votes_by_party: &HashMap<String, u32>,
+    remaining_votes: u32,
+    options: &Options,
+) -> Result<Vec<SeatBounds>, DHondtError> {
+    if options.method.divisor(0).is_none() {
+        return Err(DHondtError::NoQuotients);
+    }
+
+    let order = dhondt::allocation_order(seat_count, votes_by_party, options);
+
+    let mut bounds: Vec<SeatBounds> = votes_by_party
+        .iter()
+        .map(|(party, &votes)| SeatBounds {
+            party: party.clone(),
+            votes,
+            seats: order.iter().filter(|s| &s.0 == party).count() as u32,
+            min: min_seats(seat_count, party, votes_by_party, remaining_votes, options),
+            max: max_seats(seat_count, party, votes_by_party, remaining_votes, options),
+        })
+        .collect();
+    bounds.sort_by(|a, b| b.votes.cmp(&a.votes).then(a.party.cmp(&b.party)));
+
+    Ok(bounds)
+}
+
+/// Tells, for every seat in allocation order, whether it is already secured
+/// by the party currently holding it.
+pub fn secured_seats(
+    seat_count: u32,
+    votes_by_party: &HashMap<String, u32>,
+    remaining_votes: u32,
+    options: &Options,
+) -> Result<Vec<(String, dhondt::Quotient, bool)>, DHondtError> {
+    let bounds = seat_bounds(seat_count, votes_by_party, remaining_votes, options)?;
+    let mut seats: HashMap<String, u32> = HashMap::new();
+
+    Ok(
+        dhondt::allocation_order(seat_count, votes_by_party, options)
+            .into_iter()
+            .map(|(party, quotient)| {
+                let party_seats = seats.entry(party.clone()).or_default();
+                *party_seats += 1;
+
+                let min = bounds
+                    .iter()
+                    .find(|b| b.party == party)
+                    .map_or(0, |b| b.min);
+                let secured = *party_seats <= min;
+
+                (party, quotient, secured)
+            })
+            .collect(),
+    )
+}
+
+pub fn generate_report(
+    seat_count: u32,
+    votes_by_party: &HashMap<String, u32>,
+    remaining_votes: u32,
+    options: &Options,
+) -> Result<String, DHondtError> {
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{} {}\n\n",
+        t("Votes left to count:"),
+        format_num(remaining_votes)
+    ));
+
+    let mut lines = vec![vec![
+        t("Party").into(),
+        t("Votes").into(),
+        t("Seats").into(),
+        t("Minimum").into(),
+        t("Maximum").into(),
+    ]];
+
+    for bounds in seat_bounds(seat_count, votes_by_party, remaining_votes, options)? {
+        lines.push(vec![
+            bounds.party,
+            format_num(bounds.votes),
+            bounds.seats.to_string(),
+            bounds.min.to_string(),
+            bounds.max.to_string(),
+        ]);
+    }
+
+    output.push_str(&format_table(&lines, &[true, false, false, false, false]));
+    output.push('\n');
+
+    let mut lines = vec![vec![
+        t("Seat").into(),
+        t("Party").into(),
+        t("Quotient").into(),
+        t("Status").into(),
+    ]];
+
+    for (i, (party, quotient, secured)) in
+        secured_seats(seat_count, votes_by_party, remaining_votes, options)?
+            .into_iter()
+            .enumerate()
+    {
+        lines.push(vec![
+            (i + 1).to_string(),
+            party,
+            format_decimal(quotient.value(), 2),
+            t(if secured { "Secured" } else { "In play" }).into(),
+        ]);
+    }
+
+    output.push_str(&format_table(&lines, &[false, true, false, true]));
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn votes(parties: &[(&str, u32)]) -> HashMap<String, u32> {
+        parties.iter().map(|&(p, v)| (p.to_string(), v)).collect()
+    }
+
+    fn seats(
+        seat_count: u32,
+        votes: &HashMap<String, u32>,
+        options: &Options,
+    ) -> HashMap<String, u32> {
+        let mut seats: HashMap<String, u32> = votes.keys().map(|p| (p.clone(), 0)).collect();
+        for (party, _) in dhondt::allocation_order(seat_count, votes, options) {
+            *seats.get_mut(&party).unwrap() += 1;
+        }
+
+        seats
+    }
+
+    fn options(method: Method, threshold: Option<f64>) -> Options {
+        Options {
+            method,
+            threshold,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bounds_hold_the_final_seats() {
+        let elections = [
+            votes(&[("A", 4800), ("B", 3100), ("C", 1500), ("D", 600)]),
+            votes(&[("A", 1200), ("B", 1150), ("C", 1100), ("D", 90)]),
+            votes(&[("A", 50000), ("B", 3000), ("C", 2999)]),
+        ];
+
+        for votes in &elections {
+            for method in [Method::Dhondt, Method::SainteLague] {
+                for threshold in [None, Some(5.0), Some(20.0)] {
+                    let options = options(method, threshold);
+
+                    for seat_count in [1, 3, 7, 12] {
+                        for remaining in [0, 100, 1000, 20000] {
+                            let bounds =
+                                seat_bounds(seat_count, votes, remaining, &options).unwrap();
+
+                            for bound in &bounds {
+                                assert!(bound.min <= bound.seats && bound.seats <= bound.max);
+                            }
+
+                            // Whoever gets the remaining votes, every party ends
+                            // up within its bounds
+                            for party in votes.keys() {
+                                let mut outcome = votes.clone();
+                                *outcome.get_mut(party).unwrap() += remaining;
+                                let outcome = seats(seat_count, &outcome, &options);
+
+                                for bound in &bounds {
+                                    let seats = outcome[&bound.party];
+                                    assert!(
+                                        bound.min <= seats && seats <= bound.max,
+                                        "{} got {seats} seats outside {}..={} ({method:?}, {threshold:?}, {seat_count} seats, {remaining} left, all to {party})",
+                                        bound.party,
+                                        bound.min,
+                                        bound.max,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nothing_left_to_count() {
+        let votes = votes(&[("A", 4800), ("B", 3100), ("C", 1500), ("D", 610)]);
+
+        for method in [Method::Dhondt, Method::SainteLague] {
+            for threshold in [None, Some(10.0)] {
+                let options = options(method, threshold);
+
+                for seat_count in 1..=15 {
+                    let expected = seats(seat_count, &votes, &options);
+
+                    for bound in seat_bounds(seat_count, &votes, 0, &options).unwrap() {
+                        assert_eq!(bound.seats, expected[&bound.party]);
+                        assert_eq!((bound.min, bound.max), (bound.seats, bound.seats));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ties_can_go_either_way() {
+        // A's second quotient ties with B's first for the last seat
+        let votes = votes(&[("A", 1000), ("B", 500)]);
+        let bounds = seat_bounds(2, &votes, 0, &Options::default()).unwrap();
+
+        assert_eq!((bounds[0].min, bounds[0].max), (1, 2));
+        assert_eq!((bounds[1].min, bounds[1].max), (0, 1));
+    }
+
+    #[test]
+    fn largest_remainders() {
+        let votes = votes(&[("A", 1000), ("B", 500)]);
+
+        assert!(seat_bounds(2, &votes, 0, &options(Method::HareNiemeyer, None)).is_err());
+    }
+}
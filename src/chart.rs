@@ -0,0 +1,192 @@
+use std::f64::consts::PI;
+
+use crate::{
+    apportionment::{apportion, Method},
+    election::AllocationResult,
+    html::escape,
+    metadata::Metadata,
+};
+
+/// Width of the charts, in pixels.
+const WIDTH: f64 = 600.0;
+/// Inner radius of the hemicycle, relative to its outer radius.
+const INNER_RADIUS: f64 = 0.4;
+
+/// Positions of the seats of a hemicycle (relative to its centre, with an
+/// outer radius of 1) from left to right, along with the radius of a seat.
+/// Rows are added until they fit every seat, and seats are shared among
+/// them in proportion to their length.
+fn seat_positions(seat_count: u32) -> (Vec<(f64, f64)>, f64) {
+    let mut rows = 1;
+    let (spacing, capacities) = loop {
+        let spacing = (1.0 - INNER_RADIUS) / rows as f64;
+        let capacities: Vec<(String, u32)> = (0..rows)
+            .map(|row| {
+                let radius = INNER_RADIUS + spacing * (row as f64 + 0.5);
+                (row.to_string(), (PI * radius / spacing) as u32 + 1)
+            })
+            .collect();
+
+        if capacities.iter().map(|c| c.1).sum::<u32>() >= seat_count {
+            break (spacing, capacities);
+        }
+
+        rows += 1;
+    };
+
+    let seats_per_row = apportion(Method::HareNiemeyer, seat_count, &capacities)
+        .expect("there are always seats and rows");
+
+    let mut seats: Vec<(f64, f64, f64)> = Vec::new();
+    for (row, &seats_in_row) in seats_per_row.iter().enumerate() {
+        let radius = INNER_RADIUS + spacing * (row as f64 + 0.5);
+
+        for seat in 0..seats_in_row {
+            let angle = match seats_in_row {
+                1 => PI / 2.0,
+                n => PI * (1.0 - seat as f64 / (n - 1) as f64),
+            };
+
+            seats.push((angle, radius * angle.cos(), radius * angle.sin()));
+        }
+    }
+
+    // From left to right, and from the inside out when in line
+    seats.sort_by(|a, b| {
+        b.0.total_cmp(&a.0)
+            .then((a.1 * a.1 + a.2 * a.2).total_cmp(&(b.1 * b.1 + b.2 * b.2)))
+    });
+
+    (
+        seats.into_iter().map(|(_, x, y)| (x, y)).collect(),
+        spacing * 0.4,
+    )
+}
+
+/// Colour of a party of the results. Parties without one get the palette
+/// colour of their position in the results, the same in the hemicycle (which
+/// leaves out the parties without seats) as in the bar chart.
+fn colour(result: &AllocationResult, metadata: &Metadata, party: &str) -> String {
+    let index = result
+        .parties
+        .iter()
+        .position(|p| p.name == party)
+        .unwrap_or_default();
+
+    metadata.colour(party, index)
+}
+
+/// SVG parliament diagram: a hemicycle with a dot per seat, coloured by
+/// party with the parties in display order from left to right, followed by
+/// a legend.
+pub fn hemicycle(result: &AllocationResult, metadata: &Metadata) -> String {
+    let parties: Vec<_> = metadata
+        .order(result)
+        .into_iter()
+        .filter(|p| p.seats > 0)
+        .collect();
+
+    let radius = WIDTH / 2.0 - 10.0;
+    let (centre_x, centre_y) = (WIDTH / 2.0, radius + 10.0);
+    let legend_y = centre_y + 30.0;
+    let height = legend_y + 22.0 * parties.len() as f64 + 10.0;
+
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{height}\" viewBox=\"0 0 {WIDTH} {height}\" font-family=\"sans-serif\" font-size=\"14\">\n"
+    );
+
+    let (positions, seat_radius) = seat_positions(result.seat_count);
+    let mut positions = positions.into_iter();
+
+    for party in &parties {
+        let colour = colour(result, metadata, &party.name);
+
+        output.push_str(&format!(
+            "<g fill=\"{colour}\"><title>{}</title>\n",
+            escape(&party.name)
+        ));
+        for (x, y) in positions.by_ref().take(party.seats as usize) {
+            output.push_str(&format!(
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\"/>\n",
+                centre_x + x * radius,
+                centre_y - y * radius,
+                seat_radius * radius
+            ));
+        }
+        output.push_str("</g>\n");
+    }
+
+    output.push_str(&format!(
+        "<text x=\"{centre_x}\" y=\"{centre_y}\" text-anchor=\"middle\" font-size=\"36\" font-weight=\"bold\">{}</text>\n",
+        result.seat_count
+    ));
+
+    for (i, party) in parties.iter().enumerate() {
+        let y = legend_y + 22.0 * i as f64;
+
+        output.push_str(&format!(
+            "<rect x=\"10\" y=\"{y}\" width=\"14\" height=\"14\" fill=\"{}\"/>\n<text x=\"32\" y=\"{}\">{} ({})</text>\n",
+            colour(result, metadata, &party.name),
+            y + 12.0,
+            escape(&party.name),
+            party.seats
+        ));
+    }
+
+    output.push_str("</svg>\n");
+
+    output
+}
+
+/// SVG bar chart comparing the share of the votes and of the seats of every
+/// party, in display order.
+pub fn share_bars(result: &AllocationResult, metadata: &Metadata) -> String {
+    let parties = metadata.order(result);
+
+    let label_width = 160.0;
+    let bar_width = WIDTH - label_width - 70.0;
+    let row_height = 40.0;
+    let height = 40.0 + row_height * parties.len() as f64;
+
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{height}\" viewBox=\"0 0 {WIDTH} {height}\" font-family=\"sans-serif\" font-size=\"12\">\n"
+    );
+
+    output.push_str(&format!(
+        "<rect x=\"{label_width}\" y=\"8\" width=\"12\" height=\"12\" fill=\"#888\" fill-opacity=\"0.45\"/>\n<text x=\"{}\" y=\"18\">Votes</text>\n<rect x=\"{}\" y=\"8\" width=\"12\" height=\"12\" fill=\"#888\"/>\n<text x=\"{}\" y=\"18\">Seats</text>\n",
+        label_width + 16.0,
+        label_width + 70.0,
+        label_width + 86.0,
+    ));
+
+    for (i, party) in parties.iter().enumerate() {
+        let y = 30.0 + row_height * i as f64;
+        let colour = colour(result, metadata, &party.name);
+        let seat_share = party.seats as f64 / result.seat_count as f64 * 100.0;
+
+        output.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n",
+            label_width - 8.0,
+            y + 20.0,
+            escape(&party.name)
+        ));
+
+        for (j, (share, opacity)) in [(party.share, 0.45), (seat_share, 1.0)]
+            .into_iter()
+            .enumerate()
+        {
+            let bar_y = y + 4.0 + 15.0 * j as f64;
+            let length = bar_width * share / 100.0;
+
+            output.push_str(&format!(
+                "<rect x=\"{label_width}\" y=\"{bar_y}\" width=\"{length:.2}\" height=\"13\" fill=\"{colour}\" fill-opacity=\"{opacity}\"/>\n<text x=\"{:.2}\" y=\"{}\">{share:.2}%</text>\n",
+                label_width + length + 4.0,
+                bar_y + 11.0,
+            ));
+        }
+    }
+
+    output.push_str("</svg>\n");
+
+    output
+}
@@ -0,0 +1,80 @@
+use crate::{
+    apportionment::{apportion, Method},
+    dhondt::DHondtError,
+    i18n::t,
+    text_table::format_table,
+};
+
+/// Computes the composition of every committee, distributing its seats among
+/// the parliamentary groups proportionally to their sizes. Each composition
+/// follows the order of `groups`.
+pub fn allocate(
+    method: Method,
+    groups: &[(String, u32)],
+    committees: &[(String, u32)],
+) -> Result<Vec<Vec<u32>>, DHondtError> {
+    if committees.is_empty() {
+        return Err(DHondtError::NoCommittees);
+    }
+
+    committees
+        .iter()
+        .map(|(_, size)| apportion(method, *size, groups))
+        .collect()
+}
+
+pub fn generate_report(
+    method: Method,
+    groups: &[(String, u32)],
+    committees: &[(String, u32)],
+    compositions: &[Vec<u32>],
+) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("{} {}\n", t("Method:"), method.name()));
+    output.push_str(&format!("{} {}\n\n", t("Committees:"), committees.len()));
+
+    let mut header = vec![t("Group").into(), t("Size").into()];
+    header.extend(committees.iter().map(|c| c.0.clone()));
+    header.push(t("Total").into());
+
+    let mut lines = vec![header];
+
+    for (i, (group, size)) in groups.iter().enumerate() {
+        let mut line = vec![group.clone(), size.to_string()];
+        line.extend(compositions.iter().map(|c| c[i].to_string()));
+        line.push(compositions.iter().map(|c| c[i]).sum::<u32>().to_string());
+
+        lines.push(line);
+    }
+
+    let mut totals = vec![
+        t("Total").into(),
+        groups.iter().map(|g| g.1).sum::<u32>().to_string(),
+    ];
+    totals.extend(committees.iter().map(|c| c.1.to_string()));
+    totals.push(committees.iter().map(|c| c.1).sum::<u32>().to_string());
+    lines.push(vec![]);
+    lines.push(totals);
+
+    let mut left_align = vec![false; lines[0].len()];
+    left_align[0] = true;
+
+    output.push_str(&format_table(&lines, &left_align));
+
+    let unrepresented: Vec<&str> = groups
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| compositions.iter().all(|c| c[*i] == 0))
+        .map(|(_, g)| g.0.as_str())
+        .collect();
+
+    if !unrepresented.is_empty() {
+        output.push_str(&format!(
+            "\nGroups without seats in any committee: {}\n",
+            unrepresented.join(", ")
+        ));
+    }
+
+    output
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use crate::{
+    apportionment::Method,
+    dhondt::{calculate_with, DHondtError, Options, PartyCandidate},
+    election::AllocationResult,
+    i18n::{locale, t, t_with},
+    numeric::{format_num, format_percent},
+    text_table::format_table,
+};
+
+/// Allocates the same votes with each of the given methods.
+pub fn compare(
+    seat_count: u32,
+    votes: &HashMap<PartyCandidate, u32>,
+    options: &Options,
+    methods: &[Method],
+) -> Result<Vec<AllocationResult>, DHondtError> {
+    methods
+        .iter()
+        .map(|&method| calculate_with(seat_count, votes, &Options { method, ..*options }))
+        .collect()
+}
+
+pub fn generate_report(results: &[AllocationResult]) -> String {
+    let mut output = String::new();
+
+    let Some(first) = results.first() else {
+        return output;
+    };
+
+    output.push_str(&format!("{} {}\n", t("Seats to assign:"), first.seat_count));
+    if let Some(threshold) = first.threshold {
+        output.push_str(&format!(
+            "{} {}\n",
+            t("Threshold:"),
+            locale().percent(threshold, None)
+        ));
+    }
+    output.push('\n');
+
+    let mut header = vec![t("Party").into(), t("Votes").into(), t("Share").into()];
+    header.extend(results.iter().map(|r| r.method.name().to_string()));
+
+    let mut lines = vec![header];
+
+    for party in &first.parties {
+        let mut line = vec![
+            party.name.clone(),
+            format_num(party.votes),
+            format_percent(party.share, 2),
+        ];
+        line.extend(results.iter().map(|r| {
+            r.party(&party.name)
+                .map_or(String::new(), |p| p.seats.to_string())
+        }));
+
+        lines.push(line);
+    }
+
+    let mut left_align = vec![false; lines[0].len()];
+    left_align[0] = true;
+
+    output.push_str(&format_table(&lines, &left_align));
+
+    let differing: Vec<&str> = first
+        .parties
+        .iter()
+        .filter(|p| {
+            results
+                .iter()
+                .any(|r| r.party(&p.name).map(|q| q.seats) != Some(p.seats))
+        })
+        .map(|p| p.name.as_str())
+        .collect();
+
+    if differing.is_empty() {
+        output.push_str(&format!("\n{}\n", t("All methods give the same seats")));
+    } else {
+        output.push_str(&format!(
+            "\n{}\n",
+            t_with(
+                "Parties whose seats depend on the method: {parties}",
+                &[("parties", &differing.join(", "))]
+            )
+        ));
+    }
+
+    output
+}
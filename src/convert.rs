@@ -0,0 +1,130 @@
+use crate::{
+    election::{party_entries, Election, PartyEntry},
+    project::{join_alliances, District, Project},
+};
+
+/// Formats vote data can be converted to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Format {
+    /// `party,name,votes` rows
+    Csv,
+    /// JSON election document
+    Json,
+    /// TOML election file
+    Toml,
+    /// CSV with one row per district and one column per party
+    Wide,
+}
+
+impl Format {
+    /// Whether the format can hold several districts.
+    pub fn has_districts(&self) -> bool {
+        matches!(self, Format::Toml | Format::Wide)
+    }
+}
+
+fn party_votes(party: &PartyEntry) -> u32 {
+    party.votes.unwrap_or(0) + party.candidates.iter().map(|c| c.votes).sum::<u32>()
+}
+
+fn write_csv(records: Vec<Vec<String>>) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer
+            .write_record(record)
+            .expect("writing to memory can't fail");
+    }
+
+    String::from_utf8(writer.into_inner().expect("writing to memory can't fail")).unwrap()
+}
+
+/// The districts of an election file, with the parties of every alliance
+/// joined under its name for the formats that can't hold alliances.
+fn joined_districts(project: &Project) -> Vec<District> {
+    project
+        .districts()
+        .into_iter()
+        .map(|mut district| {
+            let mut results = Election {
+                parties: district.parties,
+                ..Default::default()
+            }
+            .results();
+            join_alliances(&project.alliances, &mut results);
+
+            district.parties = party_entries(&results);
+            district
+        })
+        .collect()
+}
+
+/// Writes an election file (with the votes given inline) in the given
+/// format. Formats without districts only take one.
+pub fn convert(project: &Project, format: Format) -> String {
+    let districts = joined_districts(project);
+
+    match format {
+        Format::Csv => {
+            let election = Election {
+                parties: districts.iter().flat_map(|d| d.parties.clone()).collect(),
+                ..Default::default()
+            };
+
+            write_csv(
+                std::iter::once(vec!["party".into(), "name".into(), "votes".into()])
+                    .chain(
+                        election
+                            .results()
+                            .into_iter()
+                            .map(|r| vec![r.party, r.name, r.votes.to_string()]),
+                    )
+                    .collect(),
+            )
+        }
+        Format::Json => {
+            let election = Election {
+                seats: districts.first().and_then(|d| d.seats),
+                method: project.method,
+                threshold: project.threshold,
+                parties: districts.iter().flat_map(|d| d.parties.clone()).collect(),
+            };
+
+            serde_json::to_string_pretty(&election).expect("elections are always serializable")
+        }
+        Format::Toml => toml::to_string(project).expect("election files are always serializable"),
+        Format::Wide => {
+            let mut parties: Vec<&str> = Vec::new();
+            for party in districts.iter().flat_map(|d| &d.parties) {
+                if !parties.contains(&party.name.as_str()) {
+                    parties.push(&party.name);
+                }
+            }
+
+            let header = ["district", "seats"]
+                .into_iter()
+                .chain(parties.iter().copied())
+                .map(String::from)
+                .collect();
+            let rows = districts.iter().map(|district| {
+                let votes = parties.iter().map(|&name| {
+                    district
+                        .parties
+                        .iter()
+                        .find(|p| p.name == name)
+                        .map_or(String::new(), |p| party_votes(p).to_string())
+                });
+
+                [
+                    district.name.clone(),
+                    district.seats.map_or(String::new(), |s| s.to_string()),
+                ]
+                .into_iter()
+                .chain(votes)
+                .collect()
+            });
+
+            write_csv(std::iter::once(header).chain(rows).collect())
+        }
+    }
+}
@@ -1,16 +1,741 @@
-use std::error::Error;
+use std::{collections::HashMap, fmt, fs::File, str::FromStr};
 
-use crate::ui::table_columns::PartyResults;
+use csv::ByteRecord;
+use rayon::prelude::*;
 
-pub fn parse_file(path: &str) -> Result<Vec<PartyResults>, Box<dyn Error>> {
+use crate::{
+    election::{PartyEntry, PartyResults},
+    error::Error,
+    input,
+    metadata::{is_valid_colour, Metadata, PartyMetadata},
+    numeric::{parse_num, NumberFormat},
+    project::District,
+    stv::Ballot,
+    tally::StationRow,
+};
+
+fn reader(path: &str) -> Result<csv::Reader<File>, Error> {
+    csv::Reader::from_path(path).map_err(|err| Error::csv(path, err))
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Encoding {
+    /// UTF-8, falling back to Windows-1252 if the file isn't valid UTF-8
+    #[default]
+    Auto,
+    #[cfg_attr(feature = "cli", value(name = "utf-8"))]
+    Utf8,
+    /// Windows-1252 (a superset of the printable Latin-1 characters)
+    #[cfg_attr(feature = "cli", value(name = "windows-1252", alias = "latin1"))]
+    Windows1252,
+}
+
+/// A column of the input file, by header or by position (starting at 1).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Column {
+    Header(String),
+    Position(usize),
+}
+
+impl Column {
+    pub(crate) fn find(&self, headers: &[String]) -> Option<usize> {
+        match self {
+            Column::Header(header) => headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(header.trim())),
+            Column::Position(position) => Some(position - 1).filter(|&i| i < headers.len()),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Column::Header(header) => write!(f, "{header}"),
+            Column::Position(position) => write!(f, "#{position}"),
+        }
+    }
+}
+
+/// Columns holding the party, candidate and votes of every row. Without a
+/// candidate column, every row holds the votes of a party.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub party: Column,
+    pub name: Option<Column>,
+    pub votes: Column,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            party: Column::Header("party".into()),
+            name: Some(Column::Header("name".into())),
+            votes: Column::Header("votes".into()),
+        }
+    }
+}
+
+impl FromStr for ColumnMapping {
+    type Err = String;
+
+    /// Parses mappings like `party=Partido,name=Candidato,votes=3`, where
+    /// numbers are column positions.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut mapping = ColumnMapping {
+            name: None,
+            ..Default::default()
+        };
+
+        for pair in text.split(',') {
+            let (field, column) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected field=column, found '{pair}'"))?;
+            let column = match column.trim().parse::<usize>() {
+                Ok(0) => return Err("column positions start at 1".into()),
+                Ok(position) => Column::Position(position),
+                Err(_) => Column::Header(column.trim().into()),
+            };
+
+            match field.trim() {
+                "party" => mapping.party = column,
+                "name" => mapping.name = Some(column),
+                "votes" => mapping.votes = column,
+                field => {
+                    return Err(format!(
+                        "unknown field '{field}' (use party, name or votes)"
+                    ))
+                }
+            }
+        }
+
+        Ok(mapping)
+    }
+}
+
+/// How to read a results file that isn't plain `party,name,votes` CSV.
+#[derive(Clone, Debug, Default)]
+pub struct ImportOptions {
+    /// Field delimiter, guessed from the header row if not given
+    pub delimiter: Option<u8>,
+    pub encoding: Encoding,
+    pub columns: ColumnMapping,
+    /// Lines to skip before the header row (e.g. titles)
+    pub skip_rows: usize,
+    pub number_format: NumberFormat,
+    /// Add up the votes of rows repeating a party and candidate instead of
+    /// rejecting them, when the rows are imported into the UI
+    pub merge_duplicates: bool,
+}
+
+/// Decodes Windows-1252, whose 0x80-0x9F range holds punctuation instead of
+/// the Latin-1 control characters.
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž',
+        '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+        'ž', 'Ÿ',
+    ];
+
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9f => HIGH[(b - 0x80) as usize],
+            _ => b as char,
+        })
+        .collect()
+}
+
+/// The most common of the usual delimiters in a line.
+fn guess_delimiter(line: &str) -> u8 {
+    [b',', b';', b'\t', b'|']
+        .into_iter()
+        .max_by_key(|&d| (line.matches(d as char).count(), d == b','))
+        .unwrap()
+}
+
+/// Reads a file as text, skipping the first `skip_rows` lines.
+fn read_text(path: &str, options: &ImportOptions) -> Result<String, Error> {
+    let bytes = input::read(path)?;
+
+    let text = match options.encoding {
+        Encoding::Utf8 => String::from_utf8(bytes).map_err(|err| {
+            let line = err.as_bytes()[..err.utf8_error().valid_up_to()]
+                .iter()
+                .filter(|&&b| b == b'\n')
+                .count() as u64
+                + 1;
+
+            Error::Input {
+                file: path.into(),
+                line: Some(line),
+                column: None,
+                message: "invalid UTF-8 (try another encoding)".into(),
+            }
+        })?,
+        Encoding::Windows1252 => decode_windows_1252(&bytes),
+        Encoding::Auto => match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(err) => decode_windows_1252(err.as_bytes()),
+        },
+    };
+
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+
+    Ok(text.split_inclusive('\n').skip(options.skip_rows).collect())
+}
+
+fn shift_lines(err: Error, skipped: usize) -> Error {
+    match err {
+        Error::Input {
+            file,
+            line,
+            column,
+            message,
+        } => Error::Input {
+            file,
+            line: line.map(|l| l + skipped as u64),
+            column,
+            message,
+        },
+        err => err,
+    }
+}
+
+fn import_reader<'a>(text: &'a str, options: &ImportOptions) -> csv::Reader<&'a [u8]> {
+    let delimiter = options
+        .delimiter
+        .unwrap_or_else(|| guess_delimiter(text.lines().next().unwrap_or("")));
+
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+}
+
+/// Headers of a results file, read the same way as by [`parse_file_with`].
+pub fn read_headers(path: &str, options: &ImportOptions) -> Result<Vec<String>, Error> {
+    let text = read_text(path, options)?;
+    let mut rdr = import_reader(&text, options);
+
+    let headers = rdr
+        .headers()
+        .map_err(|err| shift_lines(Error::csv(path, err), options.skip_rows))?;
+
+    Ok(headers.iter().map(|h| h.trim().to_string()).collect())
+}
+
+/// Whether every column of the mapping is in the headers.
+pub fn columns_match(headers: &[String], columns: &ColumnMapping) -> bool {
+    columns.party.find(headers).is_some()
+        && columns.votes.find(headers).is_some()
+        && columns
+            .name
+            .as_ref()
+            .is_none_or(|c| c.find(headers).is_some())
+}
+
+/// How results are laid out in a CSV file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Layout {
+    /// One row per candidate (or party), as `party,name,votes`
+    #[default]
+    Long,
+    /// One row per district and one column per party
+    Wide,
+    /// One row per party and one column per district
+    Transposed,
+}
+
+/// Reads a matrix of votes by district and party (see [`Layout`]), where
+/// the first column (or row) names the districts (or parties). A `seats`
+/// column (or row) gives the seats of every district, and empty cells are
+/// parties that didn't run there.
+pub fn parse_wide(
+    path: &str,
+    options: &ImportOptions,
+    layout: Layout,
+) -> Result<Vec<District>, Error> {
+    let text = read_text(path, options)?;
+    let mut rdr = import_reader(&text, options);
+    let fail = |err: csv::Error| shift_lines(Error::csv(path, err), options.skip_rows);
+
+    // Every cell along with its line and column
+    let mut cells: Vec<Vec<(u64, u64, String)>> = Vec::new();
+    let mut records = vec![rdr.headers().map_err(fail)?.clone()];
+    for result in rdr.records() {
+        records.push(result.map_err(fail)?);
+    }
+
+    for record in &records {
+        let line = record.position().map_or(1, |p| p.line()) + options.skip_rows as u64;
+
+        cells.push(
+            record
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| (line, column as u64 + 1, cell.trim().to_string()))
+                .collect(),
+        );
+    }
+
+    if layout == Layout::Transposed {
+        let width = cells.iter().map(Vec::len).max().unwrap_or(0);
+        cells = (0..width)
+            .map(|column| {
+                // Short rows have empty cells at the end, so that the rest
+                // keep their party
+                cells
+                    .iter()
+                    .map(|row| match row.get(column) {
+                        Some(cell) => cell.clone(),
+                        None => (
+                            row.first().map_or(1, |c| c.0),
+                            column as u64 + 1,
+                            String::new(),
+                        ),
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    let Some((header, rows)) = cells.split_first() else {
+        return Ok(Vec::new());
+    };
+
+    let seats_column = header
+        .iter()
+        .position(|(_, _, h)| h.eq_ignore_ascii_case("seats"));
+    let number = |(line, column, text): &(u64, u64, String)| {
+        parse_num(text, options.number_format).ok_or_else(|| Error::Input {
+            file: path.into(),
+            line: Some(*line),
+            column: Some(*column),
+            message: format!("invalid number '{text}'"),
+        })
+    };
+
+    let mut districts = Vec::new();
+
+    for row in rows {
+        if row.iter().all(|(_, _, cell)| cell.is_empty()) {
+            continue;
+        }
+
+        let mut district = District {
+            name: row.first().map_or(String::new(), |c| c.2.clone()),
+            ..Default::default()
+        };
+
+        for (i, cell) in row.iter().enumerate().skip(1) {
+            if cell.2.is_empty() {
+                continue;
+            }
+
+            if Some(i) == seats_column {
+                district.seats = Some(number(cell)?);
+                continue;
+            }
+
+            district.parties.push(PartyEntry {
+                name: header.get(i).map_or(String::new(), |h| h.2.clone()),
+                votes: Some(number(cell)?),
+                candidates: Vec::new(),
+            });
+        }
+
+        districts.push(district);
+    }
+
+    Ok(districts)
+}
+
+pub fn parse_file(path: &str) -> Result<Vec<PartyResults>, Error> {
+    parse_file_with(path, &ImportOptions::default())
+}
+
+pub fn parse_file_with(path: &str, options: &ImportOptions) -> Result<Vec<PartyResults>, Error> {
+    Ok(parse_rows_with(path, options)?
+        .into_iter()
+        .map(|(_, row)| row)
+        .collect())
+}
+
+/// Reads a results file along with the line every row was found at.
+pub fn parse_rows_with(
+    path: &str,
+    options: &ImportOptions,
+) -> Result<Vec<(u64, PartyResults)>, Error> {
     let mut candidates = Vec::new();
 
-    let mut rdr = csv::Reader::from_path(path)?;
-    for result in rdr.deserialize() {
-        let record: PartyResults = result?;
+    let text = read_text(path, options)?;
+    let mut rdr = import_reader(&text, options);
+    let fail = |err: csv::Error| shift_lines(Error::csv(path, err), options.skip_rows);
+
+    let headers: Vec<String> = rdr
+        .headers()
+        .map_err(fail)?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+
+    let find = |column: &Column| {
+        column.find(&headers).ok_or_else(|| Error::Input {
+            file: path.into(),
+            line: Some(options.skip_rows as u64 + 1),
+            column: None,
+            message: format!("missing column {column} (found {})", headers.join(", ")),
+        })
+    };
+
+    let party_column = find(&options.columns.party)?;
+    let name_column = options.columns.name.as_ref().map(find).transpose()?;
+    let votes_column = find(&options.columns.votes)?;
+
+    for result in rdr.records() {
+        let record = result.map_err(fail)?;
+        let line = record.position().map_or(0, |p| p.line()) + options.skip_rows as u64;
 
-        candidates.push(record);
+        // Rows of empty fields are common at the end of spreadsheet exports
+        if record.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+
+        let field = |column: usize| record.get(column).unwrap_or("").trim();
+        let votes = field(votes_column);
+
+        candidates.push((
+            line,
+            PartyResults {
+                party: field(party_column).into(),
+                name: name_column.map_or("", field).into(),
+                votes: parse_num(votes, options.number_format).ok_or_else(|| Error::Input {
+                    file: path.into(),
+                    line: Some(line),
+                    column: Some(votes_column as u64 + 1),
+                    message: format!("invalid number of votes '{votes}'"),
+                })?,
+            },
+        ));
     }
 
     Ok(candidates)
 }
+
+#[derive(serde::Deserialize)]
+struct PartySeats {
+    party: String,
+    seats: u32,
+}
+
+pub fn parse_seats(path: &str) -> Result<Vec<(String, u32)>, Error> {
+    let mut parties = Vec::new();
+
+    let mut rdr = reader(path)?;
+    for result in rdr.deserialize() {
+        let record: PartySeats = result.map_err(|err| Error::csv(path, err))?;
+
+        parties.push((record.party, record.seats));
+    }
+
+    Ok(parties)
+}
+
+#[derive(serde::Deserialize)]
+struct PartyStyle {
+    party: String,
+    #[serde(default, alias = "color")]
+    colour: Option<String>,
+}
+
+/// Reads a party metadata file (`party,colour`), with the parties in the
+/// order they should be shown.
+pub fn parse_metadata(path: &str) -> Result<Metadata, Error> {
+    let mut parties = Vec::new();
+
+    let mut rdr = reader(path)?;
+    let headers = rdr.headers().map_err(|err| Error::csv(path, err))?.clone();
+    for result in rdr.records() {
+        let record = result.map_err(|err| Error::csv(path, err))?;
+        let line = record.position().map(|p| p.line());
+        let record: PartyStyle = record
+            .deserialize(Some(&headers))
+            .map_err(|err| Error::csv(path, err))?;
+
+        let colour = record.colour.filter(|c| !c.trim().is_empty());
+        if let Some(ref colour) = colour {
+            if !is_valid_colour(colour.trim()) {
+                return Err(Error::Input {
+                    file: path.into(),
+                    line,
+                    column: None,
+                    message: format!("{colour} isn't a colour (use #rrggbb, #rgb or a name)"),
+                });
+            }
+        }
+
+        parties.push(PartyMetadata {
+            party: record.party,
+            colour: colour.map(|c| c.trim().to_string()),
+        });
+    }
+
+    Ok(Metadata { parties })
+}
+
+#[derive(serde::Deserialize)]
+struct CommitteeSize {
+    committee: String,
+    size: u32,
+}
+
+pub fn parse_committees(path: &str) -> Result<Vec<(String, u32)>, Error> {
+    let mut committees = Vec::new();
+
+    let mut rdr = reader(path)?;
+    for result in rdr.deserialize() {
+        let record: CommitteeSize = result.map_err(|err| Error::csv(path, err))?;
+
+        committees.push((record.committee, record.size));
+    }
+
+    Ok(committees)
+}
+
+pub fn parse_list(path: &str) -> Result<Vec<String>, Error> {
+    let mut items = Vec::new();
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|err| Error::csv(path, err))?;
+    for result in rdr.records() {
+        let record = result.map_err(|err| Error::csv(path, err))?;
+
+        if let Some(item) = record.get(0).map(str::trim).filter(|i| !i.is_empty()) {
+            items.push(item.into());
+        }
+    }
+
+    Ok(items)
+}
+
+#[derive(serde::Deserialize)]
+struct PartyPreference {
+    party: String,
+    portfolio: String,
+}
+
+pub fn parse_preferences(path: &str) -> Result<HashMap<String, Vec<String>>, Error> {
+    let mut preferences: HashMap<String, Vec<String>> = HashMap::new();
+
+    let mut rdr = reader(path)?;
+    for result in rdr.deserialize() {
+        let record: PartyPreference = result.map_err(|err| Error::csv(path, err))?;
+
+        preferences
+            .entry(record.party)
+            .or_default()
+            .push(record.portfolio);
+    }
+
+    Ok(preferences)
+}
+
+/// Reads ranked ballots, one per row with candidate names in order of
+/// preference. Candidates are numbered in order of first appearance.
+pub fn parse_ballots(path: &str) -> Result<(Vec<String>, Vec<Ballot>), Error> {
+    let mut candidates: Vec<String> = Vec::new();
+    let mut ballots: Vec<Ballot> = Vec::new();
+    let mut ballot_indices: HashMap<Vec<usize>, usize> = HashMap::new();
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|err| Error::csv(path, err))?;
+    for result in rdr.records() {
+        let record = result.map_err(|err| Error::csv(path, err))?;
+
+        let mut preferences = Vec::new();
+        for name in record.iter().map(str::trim).filter(|n| !n.is_empty()) {
+            let index = match candidates.iter().position(|c| c == name) {
+                Some(index) => index,
+                None => {
+                    candidates.push(name.into());
+                    candidates.len() - 1
+                }
+            };
+
+            if !preferences.contains(&index) {
+                preferences.push(index);
+            }
+        }
+
+        if preferences.is_empty() {
+            continue;
+        }
+
+        match ballot_indices.get(&preferences) {
+            Some(&i) => ballots[i].count += 1,
+            None => {
+                ballot_indices.insert(preferences.clone(), ballots.len());
+                ballots.push(Ballot {
+                    count: 1,
+                    preferences,
+                });
+            }
+        }
+    }
+
+    Ok((candidates, ballots))
+}
+
+#[derive(serde::Deserialize)]
+struct TallyRecord {
+    #[serde(default)]
+    station: String,
+    party: String,
+    name: String,
+    #[serde(default)]
+    votes: Option<u32>,
+}
+
+/// Records of a tally sheet read at a time.
+const TALLY_BATCH: usize = 1 << 16;
+
+fn parse_tally_record(
+    path: &str,
+    headers: &ByteRecord,
+    record: &ByteRecord,
+) -> Result<StationRow, Error> {
+    let line = record.position().map_or(0, |p| p.line());
+    let record: TallyRecord = record
+        .deserialize(Some(headers))
+        .map_err(|err| Error::csv(path, err))?;
+
+    Ok(StationRow {
+        line,
+        station: record.station,
+        party: record.party,
+        name: record.name,
+        votes: record.votes,
+    })
+}
+
+/// Reads a tally sheet in batches of rows, so that files with millions of
+/// them never have to be held in memory at once. The records of every batch
+/// are parsed in parallel.
+pub fn parse_tally_batches(
+    path: &str,
+    mut batch: impl FnMut(Vec<StationRow>),
+) -> Result<(), Error> {
+    let mut rdr = reader(path)?;
+    let headers = rdr
+        .byte_headers()
+        .map_err(|err| Error::csv(path, err))?
+        .clone();
+
+    let mut records = Vec::with_capacity(TALLY_BATCH);
+    loop {
+        let mut record = ByteRecord::new();
+        let more = rdr
+            .read_byte_record(&mut record)
+            .map_err(|err| Error::csv(path, err))?;
+
+        if more {
+            records.push(record);
+        }
+
+        if records.len() == TALLY_BATCH || (!more && !records.is_empty()) {
+            let rows: Vec<Result<StationRow, Error>> = records
+                .par_iter()
+                .map(|record| parse_tally_record(path, &headers, record))
+                .collect();
+
+            // Report the first error in the file, whichever thread found it
+            batch(rows.into_iter().collect::<Result<_, _>>()?);
+            records.clear();
+        }
+
+        if !more {
+            return Ok(());
+        }
+    }
+}
+
+/// Reads a tally sheet (`station,party,name,votes`) or a file of individual
+/// ballots, where every row without a `votes` column counts as one vote.
+pub fn parse_tally(path: &str) -> Result<Vec<StationRow>, Error> {
+    let mut rows = Vec::new();
+
+    parse_tally_batches(path, |batch| rows.extend(batch))?;
+
+    Ok(rows)
+}
+
+#[derive(serde::Deserialize)]
+struct StationVoters {
+    station: String,
+    registered: u32,
+}
+
+pub fn parse_stations(path: &str) -> Result<Vec<(String, u32)>, Error> {
+    let mut stations = Vec::new();
+
+    let mut rdr = reader(path)?;
+    for result in rdr.deserialize() {
+        let record: StationVoters = result.map_err(|err| Error::csv(path, err))?;
+
+        stations.push((record.station, record.registered));
+    }
+
+    Ok(stations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str, layout: Layout) -> Vec<District> {
+        let path = std::env::temp_dir().join(format!("dhondt-wide-{}.csv", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+
+        let districts = parse_wide(path.to_str().unwrap(), &ImportOptions::default(), layout);
+        std::fs::remove_file(&path).unwrap();
+
+        districts.unwrap()
+    }
+
+    fn votes(district: &District) -> Vec<(&str, u32)> {
+        district
+            .parties
+            .iter()
+            .map(|p| (p.name.as_str(), p.votes.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let wide = parse(
+            "district,seats,A,B,C\nD1,3,100,50,30\nD2,4,200\n",
+            Layout::Wide,
+        );
+        assert_eq!(votes(&wide[1]), [("A", 200)]);
+
+        // B has no votes in D2
+        let transposed = parse(
+            "party,D1,D2\nA,100,200\nB,50\nC,30,90\nseats,3,4\n",
+            Layout::Transposed,
+        );
+        assert_eq!(transposed.len(), 2);
+        assert_eq!(transposed[0].seats, Some(3));
+        assert_eq!(votes(&transposed[0]), [("A", 100), ("B", 50), ("C", 30)]);
+        assert_eq!(transposed[1].seats, Some(4));
+        assert_eq!(votes(&transposed[1]), [("A", 200), ("C", 90)]);
+    }
+}
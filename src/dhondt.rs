@@ -1,18 +1,201 @@
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    apportionment::{award_order, Method},
+    election::{AllocationResult, CandidateResult, PartyAllocation, SeatAward, Tie},
+    i18n::t_with,
+};
 
 #[derive(Debug)]
 pub enum DHondtError {
     ZeroSeats,
     NoVotes,
     NoParties,
+    NoPortfolios,
+    NoCommittees,
+    NoPartiesAboveThreshold,
+    NoQuotients,
+}
+
+impl DHondtError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DHondtError::ZeroSeats => "E-ZERO-SEATS",
+            DHondtError::NoVotes => "E-NO-VOTES",
+            DHondtError::NoParties => "E-NO-PARTIES",
+            DHondtError::NoPortfolios => "E-NO-PORTFOLIOS",
+            DHondtError::NoCommittees => "E-NO-COMMITTEES",
+            DHondtError::NoPartiesAboveThreshold => "E-THRESHOLD",
+            DHondtError::NoQuotients => "E-NO-QUOTIENTS",
+        }
+    }
 }
 
+impl fmt::Display for DHondtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DHondtError::NoParties => "please input some parties first",
+            DHondtError::NoVotes => "no parties have any votes",
+            DHondtError::ZeroSeats => "can't distribute zero seats",
+            DHondtError::NoPortfolios => "there are no portfolios to allocate",
+            DHondtError::NoCommittees => "there are no committees to fill",
+            DHondtError::NoPartiesAboveThreshold => "no parties with votes reach the threshold",
+            DHondtError::NoQuotients => {
+                "seat bounds need a highest-averages method (D'Hondt or Sainte-Laguë)"
+            }
+        })
+    }
+}
+
+impl std::error::Error for DHondtError {}
+
 pub type PartyCandidate = (String, String);
 
+/// A highest-averages quotient (`votes / divisor`), kept as a fraction so
+/// that comparisons are exact: 7 / 2 is above 9 / 3, and only equal
+/// fractions are ties.
+#[derive(Clone, Copy, Debug)]
+pub struct Quotient {
+    votes: u32,
+    divisor: u32,
+}
+
+impl Quotient {
+    pub fn new(votes: u32, divisor: u32) -> Self {
+        Self { votes, divisor }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.votes as f64 / self.divisor as f64
+    }
+}
+
+impl PartialEq for Quotient {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Quotient {}
+
+impl PartialOrd for Quotient {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Quotient {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.votes as u64 * other.divisor as u64).cmp(&(other.votes as u64 * self.divisor as u64))
+    }
+}
+
+pub fn quotient(votes: u32, seats: u32) -> Quotient {
+    Quotient::new(votes, seats + 1)
+}
+
+/// How tied quotients are resolved. Parties with more votes always go first;
+/// this decides between parties with the same votes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TieBreak {
+    /// More votes, then the first party by name
+    #[default]
+    MostVotes,
+    /// A reproducible drawing of lots from a seed
+    Lot,
+}
+
+/// Position of a party in a drawing of lots (FNV-1a of the seed and the
+/// name), so that the same seed always gives the same draw.
+fn lot(seed: u64, party: &str) -> u64 {
+    seed.to_le_bytes()
+        .iter()
+        .chain(party.as_bytes())
+        .fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// Parties sorted by votes and then by name (or by lot), which is the order
+/// ties are broken in.
+fn sorted_parties(votes_by_party: &HashMap<String, u32>, options: &Options) -> Vec<(String, u32)> {
+    let mut parties: Vec<(String, u32)> = votes_by_party
+        .iter()
+        .map(|(p, v)| (p.clone(), *v))
+        .collect();
+    parties.sort_by(|a, b| {
+        b.1.cmp(&a.1).then(match options.tie_break {
+            TieBreak::MostVotes => a.0.cmp(&b.0),
+            TieBreak::Lot => lot(options.seed, &a.0).cmp(&lot(options.seed, &b.0)),
+        })
+    });
+
+    parties
+}
+
+/// Whether `votes` out of `total_votes` reach the threshold of `options`.
+pub fn reaches_threshold(votes: u32, total_votes: u64, options: &Options) -> bool {
+    options
+        .threshold
+        .is_none_or(|t| votes as f64 / total_votes as f64 * 100.0 >= t)
+}
+
+/// Awards seats one at a time to the eligible party with the highest
+/// quotient, returning each seat's party and the quotient it was won with.
+/// Ties are broken as in [`calculate_with`]. Largest remainders have no
+/// quotients, so they give no seats here.
+pub fn allocation_order(
+    seat_count: u32,
+    votes_by_party: &HashMap<String, u32>,
+    options: &Options,
+) -> Vec<(String, Quotient)> {
+    let total_votes: u32 = votes_by_party.values().sum();
+    let eligible: Vec<(String, u32)> = sorted_parties(votes_by_party, options)
+        .into_iter()
+        .filter(|p| reaches_threshold(p.1, total_votes as u64, options))
+        .collect();
+
+    award_order(options.method, seat_count, &eligible)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(i, seat_quotient)| Some((eligible[i].0.clone(), seat_quotient?)))
+        .collect()
+}
+
+pub fn votes_by_party(votes: &HashMap<PartyCandidate, u32>) -> HashMap<String, u32> {
+    votes
+        .iter()
+        .fold(HashMap::new(), |mut acc, ((party, _candidate), votes)| {
+            *acc.entry(party.clone()).or_insert(0) += votes;
+            acc
+        })
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Options {
+    pub method: Method,
+    /// Percentage of the total votes a party needs to get any seats
+    pub threshold: Option<f64>,
+    pub tie_break: TieBreak,
+    /// Seed for drawing lots
+    pub seed: u64,
+}
+
 pub fn calculate(
     seat_count: u32,
     votes: &HashMap<PartyCandidate, u32>,
-) -> Result<HashMap<String, Vec<String>>, DHondtError> {
+) -> Result<AllocationResult, DHondtError> {
+    calculate_with(seat_count, votes, &Options::default())
+}
+
+pub fn calculate_with(
+    seat_count: u32,
+    votes: &HashMap<PartyCandidate, u32>,
+    options: &Options,
+) -> Result<AllocationResult, DHondtError> {
     if seat_count == 0 {
         return Err(DHondtError::ZeroSeats);
     }
@@ -21,60 +204,179 @@ pub fn calculate(
         return Err(DHondtError::NoParties);
     }
 
-    let votes_by_party =
-        votes
-            .iter()
-            .fold(HashMap::new(), |mut acc, ((party, _candidate), votes)| {
-                *acc.entry(party.clone()).or_insert(0) += votes;
-                acc
-            });
+    let votes_by_party = votes_by_party(votes);
 
     if votes_by_party.iter().all(|p| p.1 == &0) {
         return Err(DHondtError::NoVotes);
     }
 
-    let most_voted_party = votes_by_party.iter().max_by_key(|p| p.1).unwrap();
-    let mut seats = HashMap::new();
-    *seats.entry(most_voted_party.0.clone()).or_default() = 1;
+    let total_votes: u32 = votes_by_party.values().sum();
+    let share = |votes: u32| votes as f64 / total_votes as f64 * 100.0;
+
+    let (eligible, below_threshold): (Vec<_>, Vec<_>) = sorted_parties(&votes_by_party, options)
+        .into_iter()
+        .partition(|p| reaches_threshold(p.1, total_votes as u64, options));
+
+    if eligible.iter().all(|p| p.1 == 0) {
+        return Err(DHondtError::NoPartiesAboveThreshold);
+    }
+
+    let order: Vec<(String, Option<Quotient>)> =
+        award_order(options.method, seat_count, &eligible)?
+            .into_iter()
+            .map(|(i, seat_quotient)| (eligible[i].0.clone(), seat_quotient))
+            .collect();
+
+    // Seats and last quotient of every party, and candidates by party, so
+    // that large houses and lists don't need a pass per party
+    let mut won: HashMap<&str, (u32, Option<Quotient>)> = HashMap::new();
+    for (party, seat_quotient) in &order {
+        let entry = won.entry(party).or_default();
+        entry.0 += 1;
+        entry.1 = *seat_quotient;
+    }
+
+    // Votes without a candidate name are the party's own (as with closed
+    // lists), so they only count for the party
+    let mut candidates_by_party: HashMap<&str, Vec<CandidateResult>> = HashMap::new();
+    for ((party, name), &candidate_votes) in votes.iter().filter(|((_, name), _)| !name.is_empty())
+    {
+        candidates_by_party
+            .entry(party)
+            .or_default()
+            .push(CandidateResult {
+                name: name.clone(),
+                votes: candidate_votes,
+                share: share(candidate_votes),
+            });
+    }
+
+    let mut ties = Vec::new();
+    let mut warnings = Vec::new();
+    let mut parties = Vec::new();
+
+    for (index, (party, party_votes)) in eligible.iter().chain(below_threshold.iter()).enumerate() {
+        let (seats, last_quotient) = won.get(party.as_str()).copied().unwrap_or_default();
+
+        let mut candidates = candidates_by_party
+            .remove(party.as_str())
+            .unwrap_or_default();
+        candidates.sort_by(|a, b| b.votes.cmp(&a.votes).then(a.name.cmp(&b.name)));
+
+        if !candidates.is_empty() && seats as usize > candidates.len() {
+            warnings.push(t_with(
+                "{party} won {seats} seats but only has {candidates} candidates",
+                &[
+                    ("party", party),
+                    ("seats", &seats),
+                    ("candidates", &candidates.len()),
+                ],
+            ));
+        }
+
+        let not_elected = candidates.split_off(candidates.len().min(seats as usize));
+        let elected = candidates;
 
-    loop {
-        let assigned_seats: u32 = seats.iter().map(|p| p.1).sum();
-        if assigned_seats == seat_count {
-            break;
+        if let (Some(last_elected), Some(first_not_elected)) = (elected.last(), not_elected.first())
+        {
+            if last_elected.votes == first_not_elected.votes {
+                ties.push(Tie::Candidates {
+                    party: party.clone(),
+                    candidates: elected
+                        .iter()
+                        .chain(not_elected.iter())
+                        .filter(|c| c.votes == last_elected.votes)
+                        .map(|c| c.name.clone())
+                        .collect(),
+                });
+            }
         }
 
-        let next_seat_party = votes_by_party
+        parties.push(PartyAllocation {
+            name: party.clone(),
+            votes: *party_votes,
+            share: share(*party_votes),
+            seats,
+            below_threshold: index >= eligible.len(),
+            last_quotient: last_quotient.map(|q| q.value()),
+            elected,
+            not_elected,
+        });
+    }
+
+    if let Some((last_party, Some(last_quotient))) = order.last() {
+        let tied_parties: Vec<String> = parties
             .iter()
-            .max_by_key(|p| {
-                let votes = votes_by_party.get(p.0).unwrap();
-                let curr_seats = seats.get(p.0).unwrap_or(&0);
+            .filter(|p| {
+                &p.name != last_party
+                    && !p.below_threshold
+                    && options.method.quotient(p.votes, p.seats) == Some(*last_quotient)
+            })
+            .map(|p| p.name.clone())
+            .collect();
 
-                votes / (curr_seats + 1)
+        if !tied_parties.is_empty() {
+            ties.push(Tie::Seat {
+                seat: seat_count,
+                parties: std::iter::once(last_party.clone())
+                    .chain(tied_parties)
+                    .collect(),
+            });
+        }
+    }
+
+    Ok(AllocationResult {
+        seat_count,
+        method: options.method,
+        threshold: options.threshold,
+        total_votes,
+        seats: order
+            .iter()
+            .map(|(party, quotient)| SeatAward {
+                party: party.clone(),
+                quotient: quotient.map(|q| q.value()),
             })
-            .unwrap();
+            .collect(),
+        parties,
+        ties,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seats(seat_count: u32, votes: &[(&str, u32)]) -> Vec<(String, u32)> {
+        let votes = votes
+            .iter()
+            .map(|&(party, votes)| ((party.to_string(), String::new()), votes))
+            .collect();
 
-        *seats.entry(next_seat_party.0.clone()).or_default() += 1;
+        calculate(seat_count, &votes)
+            .unwrap()
+            .parties
+            .into_iter()
+            .map(|p| (p.name, p.seats))
+            .collect()
     }
 
-    let elected_candidates = seats
-        .iter()
-        .map(|p| {
-            let mut party_candidates: Vec<(String, u32)> = votes
-                .iter()
-                .filter(|c| c.0 .0 == *p.0)
-                .map(|c| (c.0 .1.clone(), *c.1))
-                .collect();
-            party_candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-
-            let party_elected_candidates: Vec<String> = party_candidates
-                .iter()
-                .take(*p.1 as usize)
-                .map(|c| c.0.clone())
-                .collect();
-
-            (p.0.clone(), party_elected_candidates)
-        })
-        .collect();
+    #[test]
+    fn exact_quotients() {
+        // The last seat goes to B's 7 / 2 = 3.5 over A's 9 / 3 = 3, even
+        // though A would win a tie for its votes
+        assert_eq!(
+            seats(4, &[("A", 9), ("B", 7)]),
+            [("A".to_string(), 2), ("B".to_string(), 2)]
+        );
+    }
 
-    Ok(elected_candidates)
+    #[test]
+    fn ties_go_to_more_votes() {
+        // A's 8 / 2 ties with B's 4 for the second seat
+        assert_eq!(
+            seats(2, &[("A", 8), ("B", 4)]),
+            [("A".to_string(), 2), ("B".to_string(), 0)]
+        );
+    }
 }
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+
+use crate::apportionment::Method;
+
+/// Votes received by a candidate of a party (or by a party alone, when lists
+/// are closed).
+#[derive(Clone, Debug, Deserialize)]
+pub struct PartyResults {
+    pub party: String,
+    pub name: String,
+    pub votes: u32,
+}
+
+/// An election given as a single document, with the parameters of the
+/// allocation along with the votes.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Election {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seats: Option<u32>,
+    #[serde(default)]
+    pub method: Method,
+    /// Percentage of the total votes a party needs to get any seats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+    #[serde(default)]
+    pub parties: Vec<PartyEntry>,
+}
+
+/// A party with either its total votes (closed lists) or the votes of each
+/// of its candidates.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartyEntry {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub votes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CandidateEntry {
+    pub name: String,
+    pub votes: u32,
+}
+
+/// Groups rows of results by party, in order of appearance. Rows without a
+/// candidate name are the party's own votes.
+pub fn party_entries(results: &[PartyResults]) -> Vec<PartyEntry> {
+    let mut parties: Vec<PartyEntry> = Vec::new();
+
+    for result in results {
+        let index = match parties.iter().position(|p| p.name == result.party) {
+            Some(index) => index,
+            None => {
+                parties.push(PartyEntry {
+                    name: result.party.clone(),
+                    votes: None,
+                    candidates: Vec::new(),
+                });
+                parties.len() - 1
+            }
+        };
+
+        let party = &mut parties[index];
+        if result.name.is_empty() {
+            *party.votes.get_or_insert(0) += result.votes;
+        } else {
+            party.candidates.push(CandidateEntry {
+                name: result.name.clone(),
+                votes: result.votes,
+            });
+        }
+    }
+
+    parties
+}
+
+impl Election {
+    /// Flattens the parties into rows, with party-only votes given as a
+    /// candidate without a name.
+    pub fn results(&self) -> Vec<PartyResults> {
+        self.parties
+            .iter()
+            .flat_map(|p| {
+                let party_votes = p.votes.map(|votes| PartyResults {
+                    party: p.name.clone(),
+                    name: String::new(),
+                    votes,
+                });
+
+                party_votes
+                    .into_iter()
+                    .chain(p.candidates.iter().map(|c| PartyResults {
+                        party: p.name.clone(),
+                        name: c.name.clone(),
+                        votes: c.votes,
+                    }))
+            })
+            .collect()
+    }
+}
+
+/// Outcome of a seat allocation. Parties are sorted by votes (then by name),
+/// and so are the candidates of every party.
+#[derive(Clone, Debug, Serialize)]
+pub struct AllocationResult {
+    pub seat_count: u32,
+    pub method: Method,
+    /// Percentage of the total votes a party needs to get any seats
+    pub threshold: Option<f64>,
+    pub total_votes: u32,
+    pub parties: Vec<PartyAllocation>,
+    /// Party that won each seat, in allocation order
+    pub seats: Vec<SeatAward>,
+    pub ties: Vec<Tie>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PartyAllocation {
+    pub name: String,
+    pub votes: u32,
+    /// Percentage of the total votes
+    pub share: f64,
+    pub seats: u32,
+    pub below_threshold: bool,
+    /// Quotient the party's last seat was won with
+    pub last_quotient: Option<f64>,
+    /// Candidates in order of votes (none for party-only votes)
+    pub elected: Vec<CandidateResult>,
+    pub not_elected: Vec<CandidateResult>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CandidateResult {
+    pub name: String,
+    pub votes: u32,
+    /// Percentage of the total votes
+    pub share: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SeatAward {
+    pub party: String,
+    /// Quotient the seat was won with (only for highest-averages methods)
+    pub quotient: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Tie {
+    /// The last seat was won with the same quotient as other parties' next
+    /// quotients
+    Seat { seat: u32, parties: Vec<String> },
+    /// Candidates of a party with the same votes on both sides of the last
+    /// elected position
+    Candidates {
+        party: String,
+        candidates: Vec<String>,
+    },
+}
+
+impl AllocationResult {
+    pub fn party(&self, name: &str) -> Option<&PartyAllocation> {
+        self.parties.iter().find(|p| p.name == name)
+    }
+
+    /// Whether any party has candidates, rather than only party votes.
+    pub fn has_candidates(&self) -> bool {
+        self.parties
+            .iter()
+            .any(|p| !p.elected.is_empty() || !p.not_elected.is_empty())
+    }
+
+    /// Whether `candidate` is tied with an elected candidate of `party`
+    /// without being elected themselves.
+    pub fn is_tied(&self, party: &str, candidate: &str) -> bool {
+        self.ties.iter().any(|t| match t {
+            Tie::Candidates {
+                party: tied_party,
+                candidates,
+            } => {
+                tied_party == party
+                    && candidates.iter().any(|c| c == candidate)
+                    && self
+                        .party(party)
+                        .is_some_and(|p| p.not_elected.iter().any(|c| c.name == candidate))
+            }
+            Tie::Seat { .. } => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_fields() {
+        let election: Election =
+            serde_json::from_str(r#"{ "seats": 3, "parties": [{ "name": "A", "votes": 10 }] }"#)
+                .unwrap();
+        assert_eq!(election.seats, Some(3));
+
+        // A misspelled key would otherwise leave the seats unset
+        let err = serde_json::from_str::<Election>(r#"{ "seat": 3, "parties": [] }"#).unwrap_err();
+        assert!(err.to_string().contains("unknown field `seat`"));
+
+        let err = serde_json::from_str::<Election>(
+            r#"{ "seats": 3, "parties": [{ "name": "A", "vote": 10 }] }"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown field `vote`"));
+    }
+}
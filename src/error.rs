@@ -0,0 +1,194 @@
+use std::{fmt, io};
+
+use crate::{dhondt::DHondtError, input};
+
+#[derive(Debug)]
+pub enum Error {
+    /// Invalid input data, with the position where it was found (lines and
+    /// columns start at 1)
+    Input {
+        file: String,
+        line: Option<u64>,
+        column: Option<u64>,
+        message: String,
+    },
+    Allocation(DHondtError),
+    /// Failure reading an input file
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    /// Failure writing results
+    Export {
+        path: String,
+        source: io::Error,
+    },
+}
+
+impl Error {
+    pub fn input(file: &str, message: impl Into<String>) -> Self {
+        Error::Input {
+            file: file.into(),
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn csv(file: &str, err: csv::Error) -> Self {
+        let line = err.position().map(|p| p.line());
+        let description = err.to_string();
+
+        match err.into_kind() {
+            csv::ErrorKind::Io(source) => Error::Io {
+                path: file.into(),
+                source,
+            },
+            csv::ErrorKind::Deserialize { err, .. } => Error::Input {
+                file: file.into(),
+                line,
+                column: err.field().map(|f| f + 1),
+                message: err.kind().to_string(),
+            },
+            csv::ErrorKind::UnequalLengths {
+                expected_len, len, ..
+            } => Error::Input {
+                file: file.into(),
+                line,
+                column: None,
+                message: format!("expected {expected_len} fields, found {len}"),
+            },
+            csv::ErrorKind::Utf8 { err, .. } => Error::Input {
+                file: file.into(),
+                line,
+                column: Some(err.field() as u64 + 1),
+                message: "invalid UTF-8".into(),
+            },
+            _ => Error::Input {
+                file: file.into(),
+                line,
+                column: None,
+                message: description,
+            },
+        }
+    }
+
+    pub fn json(file: &str, err: serde_json::Error) -> Self {
+        if err.is_io() {
+            return Error::Io {
+                path: file.into(),
+                source: err.into(),
+            };
+        }
+
+        let message = err.to_string();
+        // serde_json appends the position to its messages
+        let message = match message.rfind(" at line ") {
+            Some(i) => message[..i].to_string(),
+            None => message,
+        };
+
+        Error::Input {
+            file: file.into(),
+            line: Some(err.line() as u64).filter(|&l| l > 0),
+            column: Some(err.column() as u64).filter(|&c| c > 0),
+            message,
+        }
+    }
+
+    pub fn toml(file: &str, contents: &str, err: toml::de::Error) -> Self {
+        let (line, column) = match err.span() {
+            Some(span) => {
+                let before = &contents[..span.start];
+                let line = before.matches('\n').count() as u64 + 1;
+                let column = before.chars().rev().take_while(|&c| c != '\n').count() as u64 + 1;
+
+                (Some(line), Some(column))
+            }
+            None => (None, None),
+        };
+
+        Error::Input {
+            file: file.into(),
+            line,
+            column,
+            message: err.message().into(),
+        }
+    }
+
+    /// Stable identifier of the kind of error, meant for scripts.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Input { .. } => "E-INPUT",
+            Error::Allocation(err) => err.code(),
+            Error::Io { .. } => "E-IO",
+            Error::Export { .. } => "E-EXPORT",
+        }
+    }
+
+    /// The message of the error followed by its chain of causes.
+    pub fn describe(&self) -> String {
+        let mut message = self.to_string();
+
+        let mut source = std::error::Error::source(self);
+        while let Some(cause) = source {
+            message.push_str(&format!(": {cause}"));
+            source = cause.source();
+        }
+
+        message
+    }
+
+    /// Process exit code for the error (following `sysexits.h` where one
+    /// applies).
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Input { .. } => 65,
+            Error::Allocation(_) => 3,
+            Error::Io { .. } => 74,
+            Error::Export { .. } => 73,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Input {
+                file,
+                line,
+                column,
+                message,
+            } => {
+                write!(f, "{}", input::display_name(file))?;
+                if let Some(line) = line {
+                    write!(f, ":{line}")?;
+                }
+                if let Some(column) = column {
+                    write!(f, ":{column}")?;
+                }
+
+                write!(f, ": {message}")
+            }
+            Error::Allocation(_) => write!(f, "couldn't allocate seats"),
+            Error::Io { path, .. } => write!(f, "couldn't read {}", input::display_name(path)),
+            Error::Export { path, .. } => write!(f, "couldn't save results to {path}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Input { .. } => None,
+            Error::Allocation(err) => Some(err),
+            Error::Io { source, .. } | Error::Export { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<DHondtError> for Error {
+    fn from(err: DHondtError) -> Self {
+        Error::Allocation(err)
+    }
+}
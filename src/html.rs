@@ -0,0 +1,227 @@
+use crate::{
+    apportionment::Method,
+    election::{AllocationResult, Tie},
+    i18n::{locale, t, t_with},
+    numeric::{format_decimal, format_num, format_percent},
+    report::{quotient_table, ReportOptions},
+};
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { padding: 0.3em 0.8em; border-bottom: 1px solid #ddd; }
+th { text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+.swatch { display: inline-block; width: 0.9em; height: 0.9em; border-radius: 0.2em; margin-right: 0.4em; vertical-align: middle; }
+.bar { width: 12em; }
+.bar div { height: 0.9em; border-radius: 0.2em; }
+tr.below-threshold { color: #888; }
+tr.elected td { font-weight: bold; background: #f2f8f2; }
+td.won { font-weight: bold; background: #f2f8f2; }
+.notes { color: #555; }
+";
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Header row of a table, with its (English) column names translated.
+fn header(columns: &[&str]) -> String {
+    let cells: String = columns
+        .iter()
+        .map(|&column| match column {
+            "" => "<th></th>".to_string(),
+            column => format!("<th>{}</th>", escape(t(column))),
+        })
+        .collect();
+
+    format!("<tr>{cells}</tr>\n")
+}
+
+/// Writes the results of a district. `parties` are the parties of every
+/// district, so that each keeps its colour in all of them.
+fn generate_result(
+    output: &mut String,
+    result: &AllocationResult,
+    parties: &[&str],
+    options: &ReportOptions,
+) {
+    output.push_str("<p>");
+    output.push_str(&format!(
+        "{} <strong>{}</strong><br>\n",
+        escape(t("Seats to assign:")),
+        result.seat_count
+    ));
+    if result.method != Method::Dhondt {
+        output.push_str(&format!(
+            "{} {}<br>\n",
+            escape(t("Method:")),
+            escape(result.method.name())
+        ));
+    }
+    if let Some(threshold) = result.threshold {
+        output.push_str(&format!(
+            "{} {}<br>\n",
+            escape(t("Threshold:")),
+            locale().percent(threshold, None)
+        ));
+    }
+    output.push_str(&format!(
+        "{} {}</p>\n",
+        escape(t("Total of votes:")),
+        format_num(result.total_votes)
+    ));
+
+    output.push_str("<table class=\"summary\">\n");
+    output.push_str(&header(&["Party", "Votes", "Share", "Seats", ""]));
+    for party in &result.parties {
+        let index = parties.iter().position(|&p| p == party.name).unwrap();
+        let colour = options.metadata.colour(&party.name, index);
+
+        output.push_str(&format!(
+            "<tr{}><td><span class=\"swatch\" style=\"background: {colour}\"></span>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"bar\"><div style=\"width: {:.1}%; background: {colour}\"></div></td></tr>\n",
+            if party.below_threshold {
+                " class=\"below-threshold\""
+            } else {
+                ""
+            },
+            escape(&party.name),
+            format_num(party.votes),
+            format_percent(party.share, 2),
+            if party.below_threshold {
+                "-".into()
+            } else {
+                party.seats.to_string()
+            },
+            party.seats as f64 / result.seat_count as f64 * 100.0,
+        ));
+    }
+    output.push_str("</table>\n");
+
+    if result.has_candidates() {
+        output.push_str(&format!(
+            "<h3>{}</h3>\n<table class=\"candidates\">\n",
+            escape(t("Candidates"))
+        ));
+        output.push_str(&header(&["Party", "Candidate", "Votes", "Share", ""]));
+
+        for party in &result.parties {
+            let candidates = party
+                .elected
+                .iter()
+                .map(|c| (c, true))
+                .chain(party.not_elected.iter().map(|c| (c, false)));
+
+            for (candidate, elected) in candidates {
+                let mark = if elected {
+                    "✓"
+                } else if result.is_tied(&party.name, &candidate.name) {
+                    "?"
+                } else {
+                    ""
+                };
+
+                output.push_str(&format!(
+                    "<tr{}><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td>{mark}</td></tr>\n",
+                    if elected { " class=\"elected\"" } else { "" },
+                    escape(&party.name),
+                    escape(&candidate.name),
+                    format_num(candidate.votes),
+                    format_percent(candidate.share, 2),
+                ));
+            }
+        }
+        output.push_str("</table>\n");
+    }
+
+    if let Some(table) = quotient_table(result).filter(|_| options.quotients) {
+        let columns = table.first().map_or(0, |(_, quotients)| quotients.len());
+
+        output.push_str(&format!(
+            "<h3>{}</h3>\n<table class=\"quotients\">\n<tr><th>{}</th>",
+            escape(t("Quotients")),
+            escape(t("Party"))
+        ));
+        for seat in 1..=columns {
+            output.push_str(&format!("<th>{seat}</th>"));
+        }
+        output.push_str("</tr>\n");
+
+        for (party, quotients) in table {
+            output.push_str(&format!("<tr><td>{}</td>", escape(party)));
+            for (value, won) in quotients {
+                output.push_str(&format!(
+                    "<td class=\"num{}\">{}</td>",
+                    if won { " won" } else { "" },
+                    format_decimal(value, 2)
+                ));
+            }
+            output.push_str("</tr>\n");
+        }
+        output.push_str("</table>\n");
+    }
+
+    let notes: Vec<String> = result
+        .ties
+        .iter()
+        .filter_map(|tie| match tie {
+            Tie::Seat { seat, parties } => Some(escape(&t_with(
+                "Seat {seat} was tied between {parties}",
+                &[("seat", seat), ("parties", &parties.join(", "))],
+            ))),
+            Tie::Candidates { .. } => None,
+        })
+        .chain(
+            result
+                .warnings
+                .iter()
+                .map(|warning| format!("{} {}", escape(t("Warning:")), escape(warning))),
+        )
+        .collect();
+
+    if !notes.is_empty() {
+        output.push_str("<ul class=\"notes\">\n");
+        for note in notes {
+            output.push_str(&format!("<li>{note}</li>\n"));
+        }
+        output.push_str("</ul>\n");
+    }
+}
+
+/// Self-contained HTML page with the results, with a section for every
+/// district (a single result has no district name).
+pub fn generate(results: &[(String, AllocationResult)], options: &ReportOptions) -> String {
+    let mut output = String::new();
+
+    let mut parties: Vec<&str> = Vec::new();
+    for party in results.iter().flat_map(|(_, result)| &result.parties) {
+        if !parties.contains(&party.name.as_str()) {
+            parties.push(&party.name);
+        }
+    }
+
+    let title = escape(t("Seat distribution"));
+
+    output.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    output.push_str(&format!("<title>{title}</title>\n"));
+    output.push_str(&format!("<style>{STYLE}</style>\n</head>\n<body>\n"));
+    output.push_str(&format!("<h1>{title}</h1>\n"));
+
+    for (district, result) in results {
+        output.push_str("<section>\n");
+        if !district.is_empty() {
+            output.push_str(&format!("<h2>{}</h2>\n", escape(district)));
+        }
+
+        generate_result(&mut output, result, &parties, options);
+        output.push_str("</section>\n");
+    }
+
+    output.push_str("</body>\n</html>\n");
+
+    output
+}
@@ -0,0 +1,271 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        OnceLock,
+    },
+};
+
+use crate::numeric::group_digits;
+
+/// Language of the UI and the reports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[repr(u8)]
+pub enum Locale {
+    /// English
+    #[default]
+    En,
+    /// Spanish
+    Es,
+    /// Portuguese
+    Pt,
+}
+
+/// Translations of the messages into each language, keyed by the English
+/// message. Placeholders such as `{seat}` are kept as they are, and tables
+/// (e.g. `[minutes]`) hold the wording of a section.
+const CATALOGUES: [(Locale, &str); 2] = [
+    (Locale::Es, include_str!("locales/es.toml")),
+    (Locale::Pt, include_str!("locales/pt.toml")),
+];
+
+/// Separates the section of a message from the message in the keys of a
+/// parsed catalogue, as gettext does with contexts.
+const CONTEXT: char = '\u{4}';
+
+static LOCALE: AtomicU8 = AtomicU8::new(Locale::En as u8);
+
+impl Locale {
+    /// The language of a POSIX locale name (e.g. `es_ES.UTF-8`), if it has
+    /// a catalogue.
+    pub fn from_name(name: &str) -> Option<Locale> {
+        let language = name
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match language.as_str() {
+            "en" => Some(Locale::En),
+            "es" => Some(Locale::Es),
+            "pt" => Some(Locale::Pt),
+            _ => None,
+        }
+    }
+
+    /// The language of the environment (`LC_ALL`, `LC_MESSAGES` or `LANG`,
+    /// the first one set), or English.
+    pub fn from_env() -> Locale {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Locale::from_name(&value))
+            .unwrap_or_default()
+    }
+
+    fn catalogue(&self) -> Option<&'static HashMap<String, String>> {
+        static PARSED: OnceLock<Vec<(Locale, HashMap<String, String>)>> = OnceLock::new();
+
+        let catalogues = PARSED.get_or_init(|| {
+            CATALOGUES
+                .iter()
+                .map(|(locale, source)| (*locale, parse_catalogue(source)))
+                .collect()
+        });
+
+        catalogues
+            .iter()
+            .find(|(locale, _)| locale == self)
+            .map(|(_, messages)| messages)
+    }
+
+    /// Translates a message, leaving it in English if there's no
+    /// translation.
+    pub fn translate<'a>(&self, message: &'a str) -> &'a str {
+        match self.catalogue().and_then(|messages| messages.get(message)) {
+            Some(translation) => translation,
+            None => message,
+        }
+    }
+
+    /// Translates a message with the wording of a section of the catalogue
+    /// (e.g. `minutes`), or as anywhere else if the section doesn't have it.
+    pub fn translate_in<'a>(&self, context: &str, message: &'a str) -> &'a str {
+        let key = format!("{context}{CONTEXT}{message}");
+
+        match self.catalogue().and_then(|messages| messages.get(&key)) {
+            Some(translation) => translation,
+            None => self.translate(message),
+        }
+    }
+
+    /// Thousands and decimal separators. English keeps the spaces the
+    /// reports have always grouped thousands with.
+    pub fn separators(&self) -> (&'static str, &'static str) {
+        match self {
+            Locale::En => (" ", "."),
+            Locale::Es => (".", ","),
+            Locale::Pt => (" ", ","),
+        }
+    }
+
+    /// Formats a non-negative number with the given decimal places (or as
+    /// many as it needs), grouping the thousands of its integer part.
+    pub fn number(&self, value: f64, decimals: Option<usize>) -> String {
+        let text = match decimals {
+            Some(decimals) => format!("{value:.decimals$}"),
+            None => value.to_string(),
+        };
+        let (thousands, decimal) = self.separators();
+
+        let (integer, fraction) = match text.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (text.as_str(), None),
+        };
+        let integer = group_digits(integer, thousands);
+
+        match fraction {
+            Some(fraction) => format!("{integer}{decimal}{fraction}"),
+            None => integer,
+        }
+    }
+
+    /// Formats a percentage, with a space before the sign where the
+    /// language puts one.
+    pub fn percent(&self, value: f64, decimals: Option<usize>) -> String {
+        let number = self.number(value, decimals);
+
+        match self {
+            Locale::En => format!("{number}%"),
+            Locale::Es | Locale::Pt => format!("{number} %"),
+        }
+    }
+}
+
+/// Messages of a catalogue: the keys at the top, and those of every table
+/// (a section with its own wording) prefixed with its name.
+fn parse_catalogue(source: &str) -> HashMap<String, String> {
+    let table: toml::Table = toml::from_str(source).expect("catalogues are valid TOML");
+    let mut messages = HashMap::new();
+
+    for (key, value) in table {
+        match value {
+            toml::Value::String(translation) => {
+                messages.insert(key, translation);
+            }
+            toml::Value::Table(section) => {
+                for (message, translation) in section {
+                    if let toml::Value::String(translation) = translation {
+                        messages.insert(format!("{key}{CONTEXT}{message}"), translation);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    messages
+}
+
+/// Sets the language of the UI and the reports for the rest of the run.
+pub fn set_locale(locale: Locale) {
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+/// The language of the UI and the reports.
+pub fn locale() -> Locale {
+    match LOCALE.load(Ordering::Relaxed) {
+        1 => Locale::Es,
+        2 => Locale::Pt,
+        _ => Locale::En,
+    }
+}
+
+/// Translates a message into the language of the UI and the reports.
+pub fn t(message: &str) -> &str {
+    locale().translate(message)
+}
+
+/// Translates a message and fills in its placeholders (`{name}`) with the
+/// given values.
+pub fn t_with(message: &str, values: &[(&str, &dyn Display)]) -> String {
+    fill_in(t(message), values)
+}
+
+/// Fills in the placeholders (`{name}`) of a translated message.
+pub fn fill_in(text: &str, values: &[(&str, &dyn Display)]) -> String {
+    values.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), &value.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Placeholders (`{name}`) of a message, in order.
+    fn placeholders(message: &str) -> Vec<&str> {
+        message
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn catalogues_translate_the_same_messages() {
+        let es = Locale::Es.catalogue().unwrap();
+        let pt = Locale::Pt.catalogue().unwrap();
+
+        let mut es_keys: Vec<&String> = es.keys().collect();
+        let mut pt_keys: Vec<&String> = pt.keys().collect();
+        es_keys.sort();
+        pt_keys.sort();
+        assert_eq!(es_keys, pt_keys);
+
+        for (message, translation) in es.iter().chain(pt) {
+            let mut expected = placeholders(message);
+            let mut found = placeholders(translation);
+            expected.sort();
+            found.sort();
+            assert_eq!(expected, found, "{translation:?}");
+        }
+    }
+
+    #[test]
+    fn numbers_use_the_separators_of_the_language() {
+        assert_eq!(Locale::En.number(1234567.891, Some(2)), "1 234 567.89");
+        assert_eq!(Locale::Es.number(1234567.891, Some(2)), "1.234.567,89");
+        assert_eq!(Locale::Pt.number(1234567.891, Some(2)), "1 234 567,89");
+        assert_eq!(Locale::Es.number(999.0, Some(0)), "999");
+        assert_eq!(Locale::Es.number(3.5, None), "3,5");
+        assert_eq!(Locale::En.percent(5.0, None), "5%");
+        assert_eq!(Locale::Pt.percent(12.345, Some(2)), "12,35 %");
+    }
+
+    #[test]
+    fn locale_names() {
+        assert_eq!(Locale::from_name("es_ES.UTF-8"), Some(Locale::Es));
+        assert_eq!(Locale::from_name("pt-BR"), Some(Locale::Pt));
+        assert_eq!(Locale::from_name("C"), None);
+    }
+
+    #[test]
+    fn untranslated_messages_stay_in_english() {
+        assert_eq!(Locale::Es.translate("Not a message"), "Not a message");
+        assert_eq!(Locale::En.translate("Votes"), "Votes");
+        assert_eq!(Locale::Pt.translate("Votes"), "Votos");
+    }
+
+    #[test]
+    fn sections_have_their_own_wording() {
+        assert_eq!(Locale::Es.translate("Party"), "Partido");
+        assert_eq!(Locale::Es.translate_in("minutes", "Party"), "Candidatura");
+        assert_eq!(Locale::Es.translate_in("minutes", "Votes"), "Votos");
+        assert_eq!(Locale::En.translate_in("minutes", "Party"), "Party");
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    io::{self, Read},
+    sync::Mutex,
+};
+
+use crate::error::Error;
+
+/// Path that stands for the standard input.
+pub const STDIN: &str = "-";
+
+/// Standard input, kept once read so that it can be sniffed before parsing.
+static STDIN_CONTENTS: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+
+/// Name of an input path in messages.
+pub fn display_name(path: &str) -> &str {
+    if path == STDIN {
+        "<stdin>"
+    } else {
+        path
+    }
+}
+
+/// Reads a whole file, or the standard input for `-`.
+pub fn read(path: &str) -> Result<Vec<u8>, Error> {
+    let io_error = |source| Error::Io {
+        path: path.into(),
+        source,
+    };
+
+    if path != STDIN {
+        return std::fs::read(path).map_err(io_error);
+    }
+
+    let mut contents = STDIN_CONTENTS.lock().unwrap();
+    if contents.is_none() {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes).map_err(io_error)?;
+        *contents = Some(bytes);
+    }
+
+    Ok(contents.clone().unwrap_or_default())
+}
+
+pub fn read_to_string(path: &str) -> Result<String, Error> {
+    String::from_utf8(read(path)?).map_err(|_| Error::input(path, "invalid UTF-8"))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    Csv,
+    Json,
+    Toml,
+    Spreadsheet,
+}
+
+/// Format of the data in a file, by its extension, or by its contents for
+/// the standard input.
+pub fn data_format(path: &str) -> Result<DataFormat, Error> {
+    if path == STDIN {
+        return Ok(sniff(&read(path)?));
+    }
+
+    let path = path.to_lowercase();
+    let extension = path.rsplit_once('.').map_or("", |(_, extension)| extension);
+
+    Ok(match extension {
+        "json" => DataFormat::Json,
+        "toml" => DataFormat::Toml,
+        "xlsx" | "xlsm" | "xls" | "ods" => DataFormat::Spreadsheet,
+        _ => DataFormat::Csv,
+    })
+}
+
+/// Guesses the format of some data: JSON starts with an object, and TOML
+/// with a table header or a `key = value` line. Anything else is CSV.
+fn sniff(bytes: &[u8]) -> DataFormat {
+    let text = String::from_utf8_lossy(bytes);
+    let first_line = text
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'));
+
+    match first_line {
+        Some(line) if line.starts_with('{') => DataFormat::Json,
+        Some(line) if line.starts_with('[') => DataFormat::Toml,
+        Some(line) => match line.split_once('=') {
+            Some((key, _))
+                if !key.trim().is_empty()
+                    && key
+                        .trim()
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '"')) =>
+            {
+                DataFormat::Toml
+            }
+            _ => DataFormat::Csv,
+        },
+        None => DataFormat::Csv,
+    }
+}
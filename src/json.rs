@@ -0,0 +1,7 @@
+use crate::{election::Election, error::Error, input};
+
+/// Reads an election document (seats, method, threshold and the votes of
+/// every party and candidate) from a JSON file (or the standard input).
+pub fn parse_election(path: &str) -> Result<Election, Error> {
+    serde_json::from_slice(&input::read(path)?).map_err(|err| Error::json(path, err))
+}
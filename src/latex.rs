@@ -0,0 +1,195 @@
+use crate::{
+    election::{AllocationResult, Tie},
+    i18n::{locale, t, t_with},
+    numeric::format_grouped,
+    report::{quotient_table, ReportOptions},
+};
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\textbackslash{}".into(),
+            '~' => "\\textasciitilde{}".into(),
+            '^' => "\\textasciicircum{}".into(),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!("\\{c}"),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+fn row(cells: &[String]) -> String {
+    format!("{} \\\\\n", cells.join(" & "))
+}
+
+/// Writes a `booktabs` table in a floating environment, with the first row
+/// of `lines` as its header.
+fn table(output: &mut String, caption: &str, columns: &str, lines: &[Vec<String>]) {
+    output.push_str("\\begin{table}[htbp]\n\\centering\n");
+    output.push_str(&format!("\\caption{{{caption}}}\n"));
+    output.push_str(&format!("\\begin{{tabular}}{{{columns}}}\n\\toprule\n"));
+
+    if let Some((header, rows)) = lines.split_first() {
+        output.push_str(&row(header));
+        output.push_str("\\midrule\n");
+        for line in rows {
+            output.push_str(&row(line));
+        }
+    }
+
+    output.push_str("\\bottomrule\n\\end{tabular}\n\\end{table}\n");
+}
+
+fn bold(text: String) -> String {
+    format!("\\textbf{{{text}}}")
+}
+
+fn generate_result(
+    output: &mut String,
+    district: &str,
+    result: &AllocationResult,
+    options: &ReportOptions,
+) {
+    let style = options.numbers;
+    let separator = style.grouping.then_some("\\,");
+    // A bare comma would be spaced as punctuation in math and tables
+    let decimal = match locale().separators().1 {
+        "," => "{,}",
+        decimal => decimal,
+    };
+    let number = |value: f64, decimals: usize| format_grouped(value, decimals, decimal, separator);
+    let caption = |title: &str| match district {
+        "" => title.to_string(),
+        district => format!("{title}: {}", escape(district)),
+    };
+
+    output.push_str(&format!(
+        "% {} seats, {}",
+        result.seat_count,
+        result.method.name()
+    ));
+    if let Some(threshold) = result.threshold {
+        output.push_str(&format!(", threshold {threshold}%"));
+    }
+    output.push('\n');
+
+    let mut lines = vec![vec![
+        t("Party").to_string(),
+        t("Votes").into(),
+        format!("{} (\\%)", t("Share")),
+        t("Seats").into(),
+    ]];
+    for party in &result.parties {
+        lines.push(vec![
+            escape(&party.name),
+            number(party.votes as f64, 0),
+            number(party.share, style.decimals),
+            if party.below_threshold {
+                "--".into()
+            } else {
+                party.seats.to_string()
+            },
+        ]);
+    }
+    lines.push(vec![
+        t("Total").into(),
+        number(result.total_votes as f64, 0),
+        String::new(),
+        result.seat_count.to_string(),
+    ]);
+
+    table(output, &caption(t("Seat distribution")), "lrrr", &lines);
+
+    if result.has_candidates() {
+        let mut lines = vec![vec![
+            t("Party").to_string(),
+            t("Candidate").into(),
+            t("Votes").into(),
+            format!("{} (\\%)", t("Share")),
+        ]];
+
+        for party in &result.parties {
+            let candidates = party
+                .elected
+                .iter()
+                .map(|c| (c, true))
+                .chain(party.not_elected.iter().map(|c| (c, false)));
+
+            for (candidate, elected) in candidates {
+                let name = escape(&candidate.name);
+
+                lines.push(vec![
+                    escape(&party.name),
+                    if elected { bold(name) } else { name },
+                    number(candidate.votes as f64, 0),
+                    number(candidate.share, style.decimals),
+                ]);
+            }
+        }
+
+        output.push('\n');
+        table(
+            output,
+            &caption(t("Candidates (elected in bold)")),
+            "llrr",
+            &lines,
+        );
+    }
+
+    if let Some(quotients) = quotient_table(result).filter(|_| options.quotients) {
+        let columns = quotients.first().map_or(0, |(_, q)| q.len());
+
+        let mut lines = vec![std::iter::once(t("Party").to_string())
+            .chain((1..=columns).map(|seat| seat.to_string()))
+            .collect::<Vec<_>>()];
+        for (party, quotients) in quotients {
+            lines.push(
+                std::iter::once(escape(party))
+                    .chain(quotients.into_iter().map(|(value, won)| {
+                        let value = number(value, style.decimals);
+                        if won {
+                            bold(value)
+                        } else {
+                            value
+                        }
+                    }))
+                    .collect(),
+            );
+        }
+
+        output.push('\n');
+        table(
+            output,
+            &caption(t("Quotients (seats won in bold)")),
+            &format!("l{}", "r".repeat(columns)),
+            &lines,
+        );
+    }
+
+    for tie in &result.ties {
+        if let Tie::Seat { seat, parties } = tie {
+            output.push_str(&format!(
+                "% {}\n",
+                t_with(
+                    "Seat {seat} was tied between {parties}",
+                    &[("seat", seat), ("parties", &parties.join(", "))]
+                )
+            ));
+        }
+    }
+    for warning in &result.warnings {
+        output.push_str(&format!("% {} {warning}\n", t("Warning:")));
+    }
+}
+
+/// LaTeX tables of the results of every district, to include in a document
+/// using the `booktabs` package.
+pub fn generate(results: &[(String, AllocationResult)], options: &ReportOptions) -> String {
+    let mut output = String::from("% Requires \\usepackage{booktabs}\n");
+
+    for (district, result) in results {
+        output.push('\n');
+        generate_result(&mut output, district, result, options);
+    }
+
+    output
+}
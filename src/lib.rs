@@ -0,0 +1,42 @@
+//! Seat allocation for elections with proportional representation.
+//!
+//! The allocation logic, the election model, report generation and the file
+//! parsers don't depend on the terminal UI, which is only built with the
+//! `tui` feature (and clipboard support with the `clipboard` feature).
+
+pub mod apportionment;
+pub mod batch;
+pub mod bounds;
+pub mod chart;
+pub mod committees;
+pub mod compare;
+pub mod convert;
+pub mod csv_parser;
+pub mod dhondt;
+pub mod election;
+pub mod error;
+pub mod html;
+pub mod i18n;
+pub mod input;
+pub mod json;
+pub mod latex;
+pub mod live;
+pub mod markdown;
+pub mod metadata;
+pub mod minutes;
+pub mod numeric;
+pub mod portfolios;
+pub mod project;
+pub mod report;
+pub mod simulate;
+#[cfg(feature = "spreadsheet")]
+pub mod spreadsheet;
+pub mod stv;
+pub mod sweep;
+pub mod tally;
+pub mod template;
+pub mod text_table;
+pub mod typst;
+#[cfg(feature = "tui")]
+pub mod ui;
+pub mod validate;
@@ -0,0 +1,136 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    bounds,
+    csv_parser::parse_tally,
+    dhondt::{self, DHondtError, Options, PartyCandidate},
+    error::Error,
+    i18n::{t, t_with},
+    numeric::{format_num, format_percent},
+    report::generate_report,
+    tally::{self, Anomaly, StationRow},
+};
+
+/// Files holding the partial count: either the given file or every CSV file
+/// in the given directory, in name order.
+fn tally_files(path: &str) -> Result<Vec<PathBuf>, Error> {
+    let path = Path::new(path);
+
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|source| Error::Io {
+            path: path.display().to_string(),
+            source,
+        })?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "csv"))
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+/// Identifies the current state of the tally files, so that changes can be
+/// detected by polling.
+pub fn tally_signature(path: &str) -> Vec<(PathBuf, u64, Option<SystemTime>)> {
+    tally_files(path)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|file| {
+            let metadata = fs::metadata(&file).ok()?;
+            Some((file, metadata.len(), metadata.modified().ok()))
+        })
+        .collect()
+}
+
+pub fn read_tally(path: &str) -> Result<Vec<StationRow>, Error> {
+    let mut rows = Vec::new();
+
+    for file in tally_files(path)? {
+        rows.extend(parse_tally(&file.to_string_lossy())?);
+    }
+
+    Ok(rows)
+}
+
+pub fn generate_progress_report(
+    seat_count: u32,
+    rows: &[StationRow],
+    stations: &[(String, u32)],
+    options: &Options,
+) -> Result<String, DHondtError> {
+    let tally = tally::tally(rows, Some(stations));
+
+    let reported: HashSet<&str> = tally
+        .votes_by_station
+        .iter()
+        .map(|s| s.0.as_str())
+        .collect();
+    let (counted, uncounted): (Vec<_>, Vec<_>) = stations
+        .iter()
+        .partition(|s| reported.contains(s.0.as_str()));
+    let remaining_votes: u32 = uncounted.iter().map(|s| s.1).sum();
+    let percentage = counted.len() as f64 / stations.len().max(1) as f64 * 100.0;
+
+    let mut parties: HashMap<PartyCandidate, u32> = HashMap::new();
+    for p in &tally.results {
+        parties.insert((p.party.clone(), p.name.clone()), p.votes);
+    }
+
+    let result = dhondt::calculate_with(seat_count, &parties, options)?;
+
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{}\n",
+        t_with(
+            "Stations counted: {counted} of {stations} ({percentage})",
+            &[
+                ("counted", &counted.len()),
+                ("stations", &stations.len()),
+                ("percentage", &format_percent(percentage, 2)),
+            ]
+        )
+    ));
+    output.push_str(&format!(
+        "{} {}\n\n",
+        t("Registered voters in uncounted stations:"),
+        format_num(remaining_votes)
+    ));
+
+    output.push_str(&generate_report(&result));
+    output.push('\n');
+
+    let votes_by_party = dhondt::votes_by_party(&parties);
+
+    output.push_str(&bounds::generate_report(
+        seat_count,
+        &votes_by_party,
+        remaining_votes,
+        options,
+    )?);
+
+    // Stations without results are expected until the count finishes
+    let anomalies: Vec<&Anomaly> = tally
+        .anomalies
+        .iter()
+        .filter(|a| !matches!(a, Anomaly::MissingStation(_)))
+        .collect();
+
+    if !anomalies.is_empty() {
+        output.push_str(&format!("\n{}\n", t("Anomalies:")));
+        for anomaly in anomalies {
+            output.push_str(&format!("  - {anomaly}\n"));
+        }
+    }
+
+    Ok(output)
+}
@@ -1,163 +1,1780 @@
-pub mod csv_parser;
-pub mod dhondt;
-pub mod numeric;
-pub mod ui;
-
-use std::{collections::HashMap, process::exit};
-
-use clap::{CommandFactory, Parser};
-use cursive::{
-    align::HAlign,
-    event::{Event, Key},
-    view::{Nameable, Resizable},
-    views::{Dialog, EditView, LinearLayout, OnEventView, PaddedView, TextView},
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, IsTerminal, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process::exit,
 };
-use cursive_table_view::TableView;
-use dhondt::{DHondtError, PartyCandidate};
-
-use crate::csv_parser::parse_file;
-use crate::ui::table_columns::{PartyResults, PartyResultsColumn};
-use ui::dialogs::{
-    add::new_party_dialog,
-    confirm::{confirm_clear, confirm_quit},
-    edit::edit_party_dialog,
-    results::{generate_report, start_calculation},
-    save::save_to_file,
+
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+#[cfg(feature = "tui")]
+use dhondt::csv_parser::{columns_match, read_headers};
+#[cfg(feature = "spreadsheet")]
+use dhondt::spreadsheet;
+use dhondt::{
+    apportionment::Method,
+    batch::{self, BatchFile},
+    bounds, chart, committees, compare,
+    convert::{self, convert},
+    csv_parser::{
+        parse_ballots, parse_committees, parse_list, parse_metadata, parse_preferences,
+        parse_rows_with, parse_seats, parse_stations, parse_tally_batches, parse_wide,
+        ColumnMapping, Encoding, ImportOptions, Layout,
+    },
+    dhondt::{self as dhondt_method, Options, PartyCandidate},
+    election::{party_entries, AllocationResult, PartyResults},
+    error::Error,
+    i18n::{self, Locale},
+    input::{data_format, DataFormat, STDIN},
+    json::parse_election,
+    metadata::Metadata,
+    minutes::{self, Minutes},
+    numeric::{NumberFormat, NumberStyle},
+    portfolios,
+    project::{join_alliances, parse_project, Alliance, District, Project},
+    report::{self, save_to_file, Format, ReportOptions},
+    simulate,
+    stv::{self, ExclusionTieBreak, SurplusMethod},
+    sweep,
+    tally::{Aggregator, Anomaly},
+    template::Template,
+    validate::{self, Issue},
 };
+use toml::value::Datetime;
 
 /// D'Hondt calculator
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Options used when no command is given, which shows the UI (or
+    /// calculates the results with -o)
+    #[command(flatten)]
+    args: Args,
+
+    /// Language of the UI and the reports (taken from LANG if not given)
+    #[arg(long, global = true, value_enum)]
+    lang: Option<Locale>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show the UI, with the given data loaded
+    Tui {
+        #[command(flatten)]
+        data: DataArgs,
+
+        /// Initial value for seat count
+        #[arg(short)]
+        count: Option<u32>,
+
+        #[command(flatten)]
+        allocation: AllocationArgs,
+    },
+
+    /// Allocate the seats and write the results
+    #[command(group(clap::ArgGroup::new("input").args(["data", "tally"]).required(true)))]
+    Calculate {
+        #[command(flatten)]
+        data: DataArgs,
+
+        /// Seats to assign (required unless the data gives them)
+        #[arg(short)]
+        count: Option<u32>,
+
+        #[command(flatten)]
+        allocation: AllocationArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        #[command(flatten)]
+        report: ReportArgs,
+
+        #[command(flatten)]
+        charts: ChartArgs,
+
+        /// Number of votes still to be counted, used to tell which seats are
+        /// already secured and which are still in play
+        #[arg(short, long)]
+        remaining: Option<u32>,
+    },
+
+    /// Check a CSV file or spreadsheet for duplicate rows, empty names, rows
+    /// without votes and variants of the same party name
+    #[command(mut_arg("data", |arg| arg.required(true)))]
+    Validate {
+        #[command(flatten)]
+        data: DataArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Write the data in another format (CSV, JSON, TOML or wide CSV)
+    #[command(group(clap::ArgGroup::new("input").args(["data", "tally"]).required(true)))]
+    Convert {
+        #[command(flatten)]
+        data: DataArgs,
+
+        /// Seats to assign, saved along with the votes
+        #[arg(short)]
+        count: Option<u32>,
+
+        #[command(flatten)]
+        allocation: AllocationArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Format to write (guessed from the extension of the output file if
+        /// not given)
+        #[arg(long, value_enum)]
+        to: Option<convert::Format>,
+    },
+
+    /// Compare the seats every party gets with different methods
+    #[command(group(clap::ArgGroup::new("input").args(["data", "tally"]).required(true)))]
+    Compare {
+        #[command(flatten)]
+        data: DataArgs,
+
+        /// Seats to assign (required unless the data gives them)
+        #[arg(short)]
+        count: Option<u32>,
+
+        #[command(flatten)]
+        allocation: AllocationArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Methods to compare
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Method::Dhondt, Method::SainteLague, Method::HareNiemeyer])]
+        methods: Vec<Method>,
+    },
+
+    /// Allocate the seats after moving votes to or from some parties
+    #[command(group(clap::ArgGroup::new("input").args(["data", "tally"]).required(true)))]
+    Simulate {
+        #[command(flatten)]
+        data: DataArgs,
+
+        /// Seats to assign (required unless the data gives them)
+        #[arg(short)]
+        count: Option<u32>,
+
+        #[command(flatten)]
+        allocation: AllocationArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Percentage points of the total votes a party gains (or loses, if
+        /// negative) from the rest, in proportion to their votes (e.g.
+        /// "Party A=+2.5"; can be repeated)
+        #[arg(long = "swing", value_name = "PARTY=POINTS", value_parser = parse_swing, required = true)]
+        swings: Vec<(String, f64)>,
+    },
+
+    /// Calculate many election files, saving the report of each one, and
+    /// write a summary of all of them
+    Batch {
+        /// Election files, directories holding them or glob patterns (e.g.
+        /// "results/*.csv")
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        #[command(flatten)]
+        read: ReadArgs,
+
+        /// Seats to assign in the files that don't give them
+        #[arg(short)]
+        count: Option<u32>,
+
+        #[command(flatten)]
+        allocation: AllocationArgs,
+
+        /// Directory to save the report of every file to
+        #[arg(long, value_name = "DIR")]
+        reports: Option<String>,
+
+        #[command(flatten)]
+        report: ReportArgs,
+
+        /// Save the summary to file (uses stdout if not given or "-")
+        #[arg(short)]
+        output: Option<String>,
+    },
+
+    /// Allocate the seats for every house size in a range
+    #[command(group(clap::ArgGroup::new("input").args(["data", "tally"]).required(true)))]
+    Sweep {
+        #[command(flatten)]
+        data: DataArgs,
+
+        #[command(flatten)]
+        allocation: AllocationArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Range of seat counts to allocate (e.g. "1..20")
+        #[arg(long, value_name = "MIN..MAX", value_parser = parse_range)]
+        seats: RangeInclusive<u32>,
+    },
+
+    /// Count ranked ballots using the Single Transferable Vote
+    Stv {
+        /// Path to CSV file with the ranked ballots (one ballot per row, most
+        /// preferred candidate first)
+        ballots: String,
+
+        /// Seats to fill
+        #[arg(short)]
+        count: u32,
+
+        /// Surplus transfer method
+        #[arg(long, value_enum, default_value_t = SurplusMethod::Gregory)]
+        surplus: SurplusMethod,
+
+        /// Tie-break used when excluding candidates
+        #[arg(long, value_enum, default_value_t = ExclusionTieBreak::Backwards)]
+        tie_break: ExclusionTieBreak,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Allocate portfolios among the parties by sequential picks
+    Portfolios {
+        /// Path to list of portfolios, in the order they are taken by default
+        portfolios: String,
+
+        /// Path to CSV file with the seats held by each party
+        #[arg(short)]
+        data: String,
+
+        /// Path to CSV file with each party's portfolio preferences, in order
+        #[arg(long)]
+        preferences: Option<String>,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Fill committees in proportion to the size of the parliamentary groups
+    Committees {
+        /// Path to CSV file with the committees and their sizes
+        committees: String,
+
+        /// Path to CSV file with the seats held by each parliamentary group
+        #[arg(short)]
+        data: String,
+
+        /// Apportionment method (D'Hondt by default)
+        #[arg(short, long, value_enum, default_value_t)]
+        method: Method,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Keep watching the tally and show the results live as the count
+    /// progresses
+    Watch {
+        /// Path to the tally, a CSV file that gets new rows appended or a
+        /// directory where new CSV files are dropped
+        tally: String,
+
+        /// Path to CSV file with the registered voters of every polling
+        /// station (station,registered)
+        #[arg(long)]
+        stations: String,
+
+        /// Seats to assign
+        #[arg(short)]
+        count: u32,
+
+        #[command(flatten)]
+        allocation: AllocationArgs,
+    },
+
+    /// Write the official minutes of the proclamation of the elected
+    /// candidates of a district
+    #[command(group(clap::ArgGroup::new("input").args(["data", "tally"]).required(true)))]
+    Minutes {
+        #[command(flatten)]
+        data: DataArgs,
+
+        /// Seats to assign (required unless the data gives them)
+        #[arg(short)]
+        count: Option<u32>,
+
+        #[command(flatten)]
+        allocation: AllocationArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Format of the minutes
+        #[arg(short, long, value_enum, default_value_t)]
+        format: minutes::Format,
+
+        /// Language of the minutes (the one of the reports if not given)
+        #[arg(long, value_enum)]
+        locale: Option<Locale>,
+
+        /// Name of the election (taken from the election file if not given)
+        #[arg(long)]
+        election: Option<String>,
+
+        /// Date of the election (taken from the election file if not given)
+        #[arg(long)]
+        date: Option<String>,
+
+        /// Blank votes (taken from the election file if not given)
+        #[arg(long)]
+        blank: Option<u32>,
+
+        /// Null votes (taken from the election file if not given)
+        #[arg(long)]
+        null: Option<u32>,
+
+        /// Name or role of someone signing the minutes (can be repeated;
+        /// the president and the secretary by default)
+        #[arg(long = "signatory", value_name = "NAME")]
+        signatories: Vec<String>,
+    },
+}
+
+/// Where the votes come from.
+#[derive(clap::Args, Debug)]
+struct DataArgs {
+    /// Path to CSV file with initial data, to a JSON election document
+    /// (with seats, method, threshold and votes) or to a TOML election file
+    /// ("-" reads any of them from stdin)
+    #[arg(short)]
+    data: Option<String>,
+
+    /// Path to CSV file with polling-station tally sheets
+    /// (station,party,name,votes) or individual ballots (party,name) to
+    /// aggregate instead of -d
+    #[arg(short, long, conflicts_with = "data")]
+    tally: Option<String>,
+
+    /// Path to CSV file with the registered voters of every polling station
+    /// (station,registered), used to check the tally
+    #[arg(long, requires = "tally")]
+    stations: Option<String>,
+
+    #[command(flatten)]
+    read: ReadArgs,
+}
+
+/// How the data files are read.
+#[derive(clap::Args, Clone, Debug)]
+struct ReadArgs {
+    /// Field delimiter of the CSV data (guessed if not given; use "tab" for
+    /// tabs)
+    #[arg(long, value_parser = parse_delimiter)]
+    delimiter: Option<u8>,
+
+    /// Character encoding of the CSV data
+    #[arg(long, value_enum, default_value_t = Encoding::Auto)]
+    encoding: Encoding,
+
+    /// Columns of the CSV data holding each field, by header or position
+    /// (e.g. "party=Partido,name=Candidato,votes=4"; without a name column,
+    /// rows are party totals)
+    #[arg(long, value_name = "MAPPING")]
+    columns: Option<ColumnMapping>,
+
+    /// Lines to skip before the header row of the CSV data
+    #[arg(long, value_name = "LINES", default_value_t = 0)]
+    skip_rows: usize,
+
+    /// How numbers are written in the CSV data
+    #[arg(long, value_enum, default_value_t = NumberFormat::Auto)]
+    numbers: NumberFormat,
+
+    /// Layout of the CSV data: one row per candidate, or a matrix of votes
+    /// with districts (or parties) as rows
+    #[arg(long, value_enum, default_value_t = Layout::Long)]
+    layout: Layout,
+
+    /// Sheet of the spreadsheet data to read (the first one by default)
+    #[arg(long)]
+    sheet: Option<String>,
+
+    /// Range of the spreadsheet data to read, with the headers in its first
+    /// row (e.g. "B3:D40")
+    #[arg(long)]
+    range: Option<String>,
+
+    /// Add up the votes of rows repeated for the same party and candidate
+    /// (otherwise they are rejected)
+    #[arg(long)]
+    merge_duplicates: bool,
+
+    /// District of the TOML election file or wide CSV data to use (all of
+    /// them are calculated if none is given and the results are saved)
+    #[arg(long)]
+    district: Option<String>,
+}
+
+/// How the seats are allocated, overriding what the data says.
+#[derive(clap::Args, Debug)]
+struct AllocationArgs {
+    /// Apportionment method (D'Hondt by default)
+    #[arg(short, long, value_enum)]
+    method: Option<Method>,
+
+    /// Percentage of the total votes a party needs to get any seats
+    #[arg(long, value_name = "PERCENT")]
+    threshold: Option<f64>,
+}
+
+/// How the results are written.
+#[derive(clap::Args, Debug)]
+struct ReportArgs {
+    /// Format of the results (by default, the one matching the extension of
+    /// the output file, or text)
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// Include the table of quotients in Markdown and HTML reports
+    #[arg(long)]
+    quotients: bool,
+
+    /// CSV file with the colour of the parties and the order to show them
+    /// in (party,colour), for HTML reports and charts
+    #[arg(long, value_name = "PATH")]
+    parties: Option<String>,
+
+    /// Template file to write the report with instead of a format (see
+    /// src/templates/report.txt for the default one)
+    #[arg(long, value_name = "PATH", conflicts_with = "format")]
+    template: Option<String>,
+
+    /// Decimal places of shares and quotients in LaTeX and Typst tables
+    #[arg(long, default_value_t = 2)]
+    decimals: usize,
+
+    /// Don't group the thousands of numbers in LaTeX and Typst tables
+    #[arg(long)]
+    no_grouping: bool,
+}
+
+/// SVG charts of the results.
+#[derive(clap::Args, Debug)]
+struct ChartArgs {
+    /// Save a hemicycle of the seats as SVG
+    #[arg(long, value_name = "PATH")]
+    hemicycle: Option<String>,
+
+    /// Save a chart comparing the share of votes and seats as SVG
+    #[arg(long, value_name = "PATH")]
+    bars: Option<String>,
+}
+
+impl ChartArgs {
+    /// Saves the charts asked for.
+    fn save(&self, result: &AllocationResult, metadata: &Metadata) -> Result<(), Error> {
+        let charts = [
+            (&self.hemicycle, chart::hemicycle as fn(&_, &_) -> String),
+            (&self.bars, chart::share_bars),
+        ];
+
+        for (path, chart) in charts {
+            if let Some(path) = path {
+                save_to_file(path, &chart(result, metadata)).map_err(|source| Error::Export {
+                    path: path.clone(),
+                    source,
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn any(&self) -> bool {
+        self.hemicycle.is_some() || self.bars.is_some()
+    }
+}
+
+impl ReportArgs {
+    fn format(&self, output_path: Option<&str>) -> Format {
+        self.format
+            .or_else(|| output_path.and_then(Format::from_path))
+            .unwrap_or_default()
+    }
+
+    fn template(&self) -> Result<Option<Template>, Error> {
+        self.template.as_deref().map(Template::load).transpose()
+    }
+
+    fn options(&self) -> Result<ReportOptions, Error> {
+        Ok(ReportOptions {
+            quotients: self.quotients,
+            metadata: match self.parties {
+                Some(ref path) => parse_metadata(path)?,
+                None => Metadata::default(),
+            },
+            numbers: NumberStyle {
+                decimals: self.decimals,
+                grouping: !self.no_grouping,
+            },
+        })
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct OutputArgs {
+    /// Save output to file (uses stdout if not given or "-")
+    #[arg(short)]
+    output: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
 struct Args {
     /// Initial value for seat count
     #[arg(short)]
     count: Option<u32>,
 
-    /// Path to CSV file with initial data
-    #[arg(short)]
-    data: Option<String>,
+    #[command(flatten)]
+    data: DataArgs,
+
+    #[command(flatten)]
+    allocation: AllocationArgs,
 
-    /// Save output to file (uses stdout if no path provided)
+    /// Check the data file for duplicate rows, empty names, rows without
+    /// votes and variants of the same party name instead of allocating seats
+    #[arg(long, requires = "data", conflicts_with_all = ["portfolios", "committees", "stv", "tally"])]
+    validate: bool,
+
+    /// Save output to file (uses stdout if no path or "-" is provided, which
+    /// is also the default without a terminal)
     #[arg(short)]
     output: Option<Option<String>>,
+
+    #[command(flatten)]
+    report: ReportArgs,
+
+    #[command(flatten)]
+    charts: ChartArgs,
+
+    /// Path to list of portfolios to allocate by sequential picks (-d must
+    /// then contain the seats held by each party)
+    #[arg(short, long)]
+    portfolios: Option<String>,
+
+    /// Path to CSV file with each party's portfolio preferences, in order
+    #[arg(long, requires = "portfolios")]
+    preferences: Option<String>,
+
+    /// Path to CSV file with committees and their sizes to fill (-d must then
+    /// contain the seats held by each parliamentary group)
+    #[arg(long, conflicts_with = "portfolios")]
+    committees: Option<String>,
+
+    /// Path to CSV file with ranked ballots to count using the Single
+    /// Transferable Vote (one ballot per row, most preferred candidate first)
+    #[arg(long, conflicts_with_all = ["data", "portfolios", "committees"])]
+    stv: Option<String>,
+
+    /// Surplus transfer method used for STV counts
+    #[arg(long, value_enum, default_value_t = SurplusMethod::Gregory)]
+    surplus: SurplusMethod,
+
+    /// Tie-break used when excluding candidates in STV counts
+    #[arg(long, value_enum, default_value_t = ExclusionTieBreak::Backwards)]
+    tie_break: ExclusionTieBreak,
+
+    /// Keep watching the tally (a file or a directory of CSV files) and show
+    /// the results live as the count progresses
+    #[arg(short, long, requires_all = ["tally", "stations", "count"], conflicts_with = "output")]
+    watch: bool,
+
+    /// Number of votes still to be counted, used to tell which seats are
+    /// already secured and which are still in play
+    #[arg(short, long, requires = "output")]
+    remaining: Option<u32>,
 }
 
-fn main() {
-    let args = Args::parse();
-
-    if args.output.is_some() && (args.count.is_none() || args.data.is_none()) {
-        let mut cmd = Args::command();
-        cmd.error(
-            clap::error::ErrorKind::MissingRequiredArgument,
-            "-c and -d are required when using -o",
-        )
-        .exit();
+fn parse_delimiter(text: &str) -> Result<u8, String> {
+    match text {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ if text.len() == 1 && text.is_ascii() => Ok(text.as_bytes()[0]),
+        _ => Err("the delimiter must be a single ASCII character".into()),
     }
+}
 
-    let initial_data: Vec<PartyResults> = match args.data {
-        Some(ref csv_path) => match parse_file(csv_path.as_str()) {
-            Ok(data) => data,
-            Err(err) => {
-                eprintln!("error: couldn't parse CSV file: {err}");
-                exit(1);
-            }
+fn parse_swing(text: &str) -> Result<(String, f64), String> {
+    let Some((party, points)) = text.rsplit_once('=') else {
+        return Err("the swing must be given as PARTY=POINTS".into());
+    };
+
+    match points.trim().parse() {
+        Ok(points) => Ok((party.trim().to_string(), points)),
+        Err(_) => Err(format!("{points} isn't a number of points")),
+    }
+}
+
+fn parse_range(text: &str) -> Result<RangeInclusive<u32>, String> {
+    let range = text
+        .split_once("..")
+        .and_then(|(min, max)| Some(min.trim().parse().ok()?..=max.trim().parse().ok()?));
+
+    match range {
+        Some(range) if *range.start() > 0 && !range.is_empty() => Ok(range),
+        Some(_) => Err("the range must go from 1 or more up to at least its start".into()),
+        None => Err("the range must be given as MIN..MAX".into()),
+    }
+}
+
+fn missing_argument(message: &str) -> ! {
+    let mut cmd = Cli::command();
+    cmd.error(clap::error::ErrorKind::MissingRequiredArgument, message)
+        .exit();
+}
+
+/// Prints the error along with its chain of causes and exits with the code
+/// matching its kind.
+fn fail(err: Error) -> ! {
+    eprintln!("error[{}]: {}", err.code(), err.describe());
+    exit(err.exit_code());
+}
+
+/// Saves the output to a file, or writes it to stdout if no path (or `-`)
+/// is given. A closed pipe (e.g. when piping to `head`) isn't an error.
+fn write_output(output_path: Option<String>, output: &str) -> Result<(), Error> {
+    match output_path.filter(|path| path != STDIN) {
+        Some(output_path) => save_to_file(&output_path, output).map_err(|source| Error::Export {
+            path: output_path,
+            source,
+        }),
+        None => match writeln!(io::stdout().lock(), "{output}") {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(Error::Export {
+                path: "<stdout>".into(),
+                source: err,
+            }),
+            _ => Ok(()),
         },
-        None => Vec::new(),
+    }
+}
+
+/// Whether the UI can be shown: the output isn't going elsewhere, and
+/// neither data nor results are being piped.
+fn is_interactive(args: &Args) -> bool {
+    args.output.is_none()
+        && args.data.data.as_deref() != Some(STDIN)
+        && io::stdout().is_terminal()
+        && io::stdin().is_terminal()
+}
+
+#[cfg(not(feature = "tui"))]
+fn no_tui() -> ! {
+    eprintln!("error: this build has no TUI support, use -o to get the results");
+    exit(1);
+}
+
+fn run_stv(args: Args) -> Result<(), Error> {
+    let Some(seat_count) = args.count else {
+        missing_argument("-c is required when using --stv");
     };
 
-    if args.output.is_some() {
-        let seat_count = args.count.unwrap();
+    let report = stv_report(&args.stv.unwrap(), seat_count, args.surplus, args.tie_break)?;
 
-        let mut parties: HashMap<PartyCandidate, u32> = HashMap::new();
-        initial_data.iter().for_each(|p| {
-            parties.insert((p.party.clone(), p.name.clone()), p.votes);
-        });
+    if let Some(output_path) = args.output {
+        return write_output(output_path, &report);
+    }
 
-        match dhondt::calculate(seat_count, &parties) {
-            Ok(results) => {
-                let output = generate_report(seat_count, &parties, &results);
-                let output_path = args.output.unwrap();
+    #[cfg(feature = "tui")]
+    {
+        dhondt::ui::run_report("STV count", report);
+        Ok(())
+    }
 
-                if output_path.is_none() {
-                    println!("{}", output);
-                } else {
-                    let output_path = output_path.unwrap();
-                    if let Err(err) = save_to_file(&output_path, &output) {
-                        eprintln!("error: couldn't save results to file: {err}");
-                        exit(1);
-                    }
+    #[cfg(not(feature = "tui"))]
+    no_tui();
+}
+
+fn stv_report(
+    ballots_path: &str,
+    seat_count: u32,
+    surplus: SurplusMethod,
+    tie_break: ExclusionTieBreak,
+) -> Result<String, Error> {
+    let (candidates, ballots) = parse_ballots(ballots_path)?;
+    let count = stv::count(seat_count, &candidates, &ballots, surplus, tie_break)?;
+
+    Ok(stv::generate_report(&count))
+}
+
+#[cfg(feature = "tui")]
+fn run_live(
+    seat_count: u32,
+    tally_path: String,
+    stations_path: &str,
+    allocation: &AllocationArgs,
+) -> Result<(), Error> {
+    let stations = parse_stations(stations_path)?;
+    let options = Options {
+        method: allocation.method.unwrap_or_default(),
+        threshold: allocation.threshold,
+        ..Default::default()
+    };
+
+    dhondt::ui::run_live(seat_count, tally_path, stations, options);
+
+    Ok(())
+}
+
+fn run_committees(
+    groups_path: &str,
+    committees_path: &str,
+    method: Method,
+    output_path: Option<String>,
+) -> Result<(), Error> {
+    let groups = parse_seats(groups_path)?;
+    let committees = parse_committees(committees_path)?;
+
+    let compositions = committees::allocate(method, &groups, &committees)?;
+
+    write_output(
+        output_path,
+        &committees::generate_report(method, &groups, &committees, &compositions),
+    )
+}
+
+fn run_portfolios(
+    seats_path: &str,
+    portfolios_path: &str,
+    preferences_path: Option<&str>,
+    output_path: Option<String>,
+) -> Result<(), Error> {
+    let parties = parse_seats(seats_path)?;
+    let portfolios = parse_list(portfolios_path)?;
+    let preferences = match preferences_path {
+        Some(path) => parse_preferences(path)?,
+        None => HashMap::new(),
+    };
+
+    let picks = portfolios::allocate(&parties, &portfolios, &preferences)?;
+
+    write_output(output_path, &portfolios::generate_log(&parties, &picks))
+}
+
+fn import_options(data: &DataArgs) -> ImportOptions {
+    ImportOptions {
+        delimiter: data.read.delimiter,
+        encoding: data.read.encoding,
+        columns: data.read.columns.clone().unwrap_or_default(),
+        skip_rows: data.read.skip_rows,
+        number_format: data.read.numbers,
+        merge_duplicates: data.read.merge_duplicates,
+    }
+}
+
+/// Reads rows of results from a CSV file or a spreadsheet, with the line
+/// (or sheet row) each one comes from.
+fn read_rows(data: &DataArgs, path: &str) -> Result<Vec<(u64, PartyResults)>, Error> {
+    if data_format(path)? != DataFormat::Spreadsheet {
+        return parse_rows_with(path, &import_options(data));
+    }
+
+    #[cfg(feature = "spreadsheet")]
+    return spreadsheet::parse_sheet(
+        path,
+        data.read.sheet.as_deref(),
+        data.read.range.as_deref(),
+        &import_options(data),
+    );
+
+    #[cfg(not(feature = "spreadsheet"))]
+    Err(Error::input(path, "this build has no spreadsheet support"))
+}
+
+fn run_validate(data: &DataArgs, output_path: Option<String>) -> Result<(), Error> {
+    let path = data.data.as_deref().unwrap();
+
+    if matches!(data_format(path)?, DataFormat::Json | DataFormat::Toml) {
+        return Err(Error::input(
+            path,
+            "only CSV files and spreadsheets can be validated",
+        ));
+    }
 
-                    return;
+    let rows = read_rows(data, path)?;
+    let validation = validate::validate(&rows);
+
+    let mut report = validate::generate_report(&validation);
+    let duplicates = validation
+        .issues
+        .iter()
+        .any(|i| matches!(i, Issue::Duplicate { .. }));
+
+    if data.read.merge_duplicates && duplicates {
+        let rows: Vec<PartyResults> = rows.into_iter().map(|(_, row)| row).collect();
+        let merged = validate::merge_duplicates(&rows);
+
+        report.push_str(&format!(
+            "\nRepeated rows will be added up, leaving {} rows\n",
+            merged.len()
+        ));
+    }
+
+    write_output(output_path, &report)?;
+
+    let errors = validation
+        .issues
+        .iter()
+        .filter(|i| i.is_error())
+        .filter(|i| !(data.read.merge_duplicates && matches!(i, Issue::Duplicate { .. })))
+        .count();
+    match errors {
+        0 => Ok(()),
+        1 => Err(Error::input(
+            path,
+            "1 issue keeps the data from being used as is",
+        )),
+        errors => Err(Error::input(
+            path,
+            format!("{errors} issues keep the data from being used as is"),
+        )),
+    }
+}
+
+/// The votes of a district, along with the seats and the blank and null
+/// votes the data gives it.
+#[derive(Default)]
+struct Votes {
+    district: String,
+    seats: Option<u32>,
+    results: Vec<PartyResults>,
+    blank: Option<u32>,
+    null: Option<u32>,
+}
+
+impl Votes {
+    fn parties(&self) -> HashMap<PartyCandidate, u32> {
+        self.results
+            .iter()
+            .map(|p| ((p.party.clone(), p.name.clone()), p.votes))
+            .collect()
+    }
+}
+
+/// Everything read from the data arguments.
+struct Data {
+    /// Name and date of the election, for election files
+    name: Option<String>,
+    date: Option<Datetime>,
+    /// Parties running together, joined under the name of the alliance
+    /// unless the data is read with `load_apart`
+    alliances: Vec<Alliance>,
+    /// Every district to calculate, or a single unnamed one for data without
+    /// districts
+    districts: Vec<Votes>,
+    options: Options,
+    anomalies: Vec<Anomaly>,
+    /// CSV data whose columns have to be picked in the UI before reading it
+    #[cfg_attr(not(feature = "tui"), allow(dead_code))]
+    pending_import: Option<(String, ImportOptions)>,
+}
+
+/// Reads the votes and settings given by the data arguments, with the
+/// parties of every alliance joined under its name. For the UI
+/// (`interactive`), only the district shown is read, and the columns of CSV
+/// data can be left for the user to pick.
+fn load(data: &DataArgs, allocation: &AllocationArgs, interactive: bool) -> Result<Data, Error> {
+    let mut loaded = read_data(data, allocation, interactive)?;
+
+    for votes in &mut loaded.districts {
+        join_alliances(&loaded.alliances, &mut votes.results);
+    }
+    check_duplicates(data, &mut loaded.districts)?;
+
+    Ok(loaded)
+}
+
+/// Reads the votes and settings given by the data arguments like `load`,
+/// but with the parties of every alliance kept apart.
+fn load_apart(data: &DataArgs, allocation: &AllocationArgs) -> Result<Data, Error> {
+    let mut loaded = read_data(data, allocation, false)?;
+    check_duplicates(data, &mut loaded.districts)?;
+
+    Ok(loaded)
+}
+
+fn read_data(
+    data: &DataArgs,
+    allocation: &AllocationArgs,
+    interactive: bool,
+) -> Result<Data, Error> {
+    let mut anomalies = Vec::new();
+    #[allow(unused_mut)]
+    let mut pending_import: Option<(String, ImportOptions)> = None;
+    let mut options = Options::default();
+    let (mut name, mut date, mut alliances) = (None, None, Vec::new());
+
+    let format = match data.data {
+        Some(ref path) => Some(data_format(path)?),
+        None => None,
+    };
+
+    let districts = match (&data.data, &data.tally) {
+        (Some(project_path), _)
+            if format == Some(DataFormat::Toml) || data.read.layout != Layout::Long =>
+        {
+            let project = if format == Some(DataFormat::Toml) {
+                parse_project(project_path)?
+            } else {
+                Project {
+                    districts: parse_wide(project_path, &import_options(data), data.read.layout)?,
+                    ..Default::default()
+                }
+            };
+            options = project.options();
+            name = project.name.clone();
+            date = project.date;
+            alliances = project.alliances.clone();
+
+            let mut districts = project.districts();
+            if let Some(ref name) = data.read.district {
+                districts.retain(|d| &d.name == name);
+
+                if districts.is_empty() {
+                    return Err(Error::input(
+                        project_path,
+                        format!("there's no district named {name}"),
+                    ));
+                }
+            } else if interactive {
+                districts.truncate(1);
+            }
+
+            districts
+                .into_iter()
+                .map(|district| {
+                    Ok(Votes {
+                        results: project.read_votes(project_path, &district)?,
+                        district: district.name,
+                        seats: district.seats,
+                        blank: district.blank,
+                        null: district.null,
+                    })
+                })
+                .collect::<Result<_, Error>>()?
+        }
+        (Some(json_path), _) if format == Some(DataFormat::Json) => {
+            let election = parse_election(json_path)?;
+            options.method = election.method;
+            options.threshold = election.threshold;
+
+            vec![Votes {
+                district: String::new(),
+                seats: election.seats,
+                results: election.results(),
+                ..Default::default()
+            }]
+        }
+        (Some(csv_path), _) => {
+            // Let the columns be picked in the UI when they can't be found
+            #[cfg(feature = "tui")]
+            if interactive && data.read.columns.is_none() && format != Some(DataFormat::Spreadsheet)
+            {
+                let import = import_options(data);
+
+                if !columns_match(&read_headers(csv_path, &import)?, &import.columns) {
+                    pending_import = Some((csv_path.clone(), import));
                 }
             }
-            Err(err) => {
-                eprintln!(
-                    "error: {}",
-                    match err {
-                        DHondtError::NoParties => "please input some parties first",
-                        DHondtError::NoVotes => "no parties have any votes",
-                        DHondtError::ZeroSeats => "can't distribute zero seats",
-                    },
-                );
-
-                exit(1);
+
+            let results = if pending_import.is_some() {
+                Vec::new()
+            } else {
+                read_rows(data, csv_path)?
+                    .into_iter()
+                    .map(|(_, row)| row)
+                    .collect()
+            };
+
+            vec![Votes {
+                district: String::new(),
+                seats: None,
+                results,
+                ..Default::default()
+            }]
+        }
+        (None, Some(tally_path)) => {
+            let stations = match data.stations {
+                Some(ref path) => Some(parse_stations(path)?),
+                None => None,
+            };
+
+            let mut aggregator = Aggregator::default();
+            parse_tally_batches(tally_path, |rows| aggregator.add(&rows))?;
+            let tally = aggregator.finish(stations.as_deref());
+            anomalies = tally.anomalies;
+
+            vec![Votes {
+                district: String::new(),
+                seats: None,
+                results: tally.results,
+                ..Default::default()
+            }]
+        }
+        (None, None) => vec![Votes {
+            district: String::new(),
+            seats: None,
+            results: Vec::new(),
+            ..Default::default()
+        }],
+    };
+
+    options.method = allocation.method.unwrap_or(options.method);
+    options.threshold = allocation.threshold.or(options.threshold);
+
+    Ok(Data {
+        name,
+        date,
+        alliances,
+        districts,
+        options,
+        anomalies,
+        pending_import,
+    })
+}
+
+/// Adds up the rows repeated for the same party and candidate if asked to,
+/// or fails on the first one.
+fn check_duplicates(data: &DataArgs, districts: &mut [Votes]) -> Result<(), Error> {
+    for votes in districts {
+        if data.read.merge_duplicates {
+            votes.results = validate::merge_duplicates(&votes.results);
+            continue;
+        }
+
+        if let Some(row) = validate::find_duplicate(&votes.results) {
+            let candidate = match row.name.as_str() {
+                "" => row.party.clone(),
+                name => format!("{name} ({})", row.party),
+            };
+
+            return Err(Error::input(
+                data.data.as_deref().unwrap_or_default(),
+                format!(
+                    "{candidate} is given more than once (find the rows with the validate command or --validate, or add them up with --merge-duplicates)"
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Takes the only district of the data (or the one picked with
+/// `--district`), for the commands that can't handle several.
+fn single_district(data: Data, data_args: &DataArgs) -> Result<(Votes, Options), Error> {
+    match <[Votes; 1]>::try_from(data.districts) {
+        Ok([votes]) => Ok((votes, data.options)),
+        Err(_) => Err(Error::input(
+            data_args.data.as_deref().unwrap_or_default(),
+            "the data has several districts, pick one with --district",
+        )),
+    }
+}
+
+fn seats_of(votes: &Votes, count: Option<u32>) -> u32 {
+    match count.or(votes.seats) {
+        Some(seat_count) => seat_count,
+        None => missing_argument("-c is required when the data doesn't give the seats"),
+    }
+}
+
+fn print_anomalies(anomalies: &[Anomaly]) {
+    for anomaly in anomalies {
+        eprintln!("warning: {anomaly}");
+    }
+}
+
+/// Calculates every district of the data and writes the results.
+fn calculate(
+    data: Data,
+    count: Option<u32>,
+    report: &ReportArgs,
+    charts: &ChartArgs,
+    remaining: Option<u32>,
+    output_path: Option<String>,
+    data_path: &str,
+) -> Result<(), Error> {
+    let format = report.format(output_path.as_deref());
+    let options = report.options()?;
+    let template = report.template()?;
+
+    if format != Format::Text && remaining.is_some() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "-r can only be used with the text format",
+            )
+            .exit();
+    }
+
+    print_anomalies(&data.anomalies);
+
+    if let [ref votes] = data.districts[..] {
+        let seat_count = seats_of(votes, count);
+        let parties = votes.parties();
+        let result = dhondt_method::calculate_with(seat_count, &parties, &data.options)?;
+
+        charts.save(&result, &options.metadata)?;
+
+        if let Some(ref path) = output_path {
+            if path.to_lowercase().ends_with(".xlsx") {
+                #[cfg(feature = "spreadsheet")]
+                return spreadsheet::save_xlsx(path, &result);
+
+                #[cfg(not(feature = "spreadsheet"))]
+                return Err(Error::input(path, "this build has no spreadsheet support"));
             }
+        }
+
+        let mut output = match (&template, format) {
+            (Some(template), _) => template.render(&votes.district, &result)?,
+            // Keep the name of the district picked from wide data
+            (None, Format::Wide) => report::generate_wide(&[(votes.district.clone(), result)]),
+            (None, format) => report::generate_with(&result, format, &options),
         };
 
-        return;
+        if let Some(remaining_votes) = remaining {
+            let votes_by_party = dhondt_method::votes_by_party(&parties);
+
+            output.push('\n');
+            output.push_str(&bounds::generate_report(
+                seat_count,
+                &votes_by_party,
+                remaining_votes,
+                &data.options,
+            )?);
+        }
+
+        return write_output(output_path, &output);
+    }
+
+    if charts.any() {
+        return Err(Error::input(
+            data_path,
+            "charts need a single district, pick one with --district",
+        ));
+    }
+
+    let results = allocate_districts(&data, count, data_path)?;
+
+    let output = match template {
+        Some(template) => template.render_districts(&results)?,
+        None => report::generate_districts_with(&results, format, &options),
+    };
+
+    write_output(output_path, &output)
+}
+
+/// Allocates the seats of every district. `-c` gives the seats of data
+/// without districts, while the seats of each district take precedence over
+/// it.
+fn allocate_districts(
+    data: &Data,
+    count: Option<u32>,
+    data_path: &str,
+) -> Result<Vec<(String, AllocationResult)>, Error> {
+    let single = data.districts.len() == 1;
+    let mut results = Vec::new();
+
+    for votes in &data.districts {
+        let seats = if single {
+            count.or(votes.seats)
+        } else {
+            votes.seats.or(count)
+        };
+
+        let Some(seat_count) = seats else {
+            return Err(Error::input(
+                data_path,
+                match votes.district.as_str() {
+                    "" => "the data doesn't give the seats to assign (use -c)".into(),
+                    district => format!("district {district} has no seats"),
+                },
+            ));
+        };
+
+        results.push((
+            votes.district.clone(),
+            dhondt_method::calculate_with(seat_count, &votes.parties(), &data.options)?,
+        ));
+    }
+
+    Ok(results)
+}
+
+fn run_tui(data: Data, count: Option<u32>) -> Result<(), Error> {
+    print_anomalies(&data.anomalies);
+
+    #[cfg(feature = "tui")]
+    {
+        let warnings: Vec<String> = data.anomalies.iter().map(|a| a.to_string()).collect();
+        let votes = data.districts.into_iter().next().unwrap();
+
+        dhondt::ui::run(
+            votes.results,
+            count.or(votes.seats),
+            data.options,
+            data.pending_import,
+            &warnings,
+        );
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "tui"))]
+    {
+        let _ = count;
+        no_tui();
+    }
+}
+
+fn run_convert(
+    data_args: &DataArgs,
+    count: Option<u32>,
+    allocation: &AllocationArgs,
+    output_path: Option<String>,
+    to: Option<convert::Format>,
+) -> Result<(), Error> {
+    let extension = output_path
+        .as_deref()
+        .and_then(|path| path.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_lowercase());
+
+    let format = match (to, extension.as_deref()) {
+        (Some(format), _) => format,
+        (None, Some("csv")) => convert::Format::Csv,
+        (None, Some("json")) => convert::Format::Json,
+        (None, Some("toml")) => convert::Format::Toml,
+        _ => missing_argument("--to is required unless the output file is .csv, .json or .toml"),
+    };
+
+    let data = load_apart(data_args, allocation)?;
+    print_anomalies(&data.anomalies);
+
+    if data.districts.len() > 1 && !format.has_districts() {
+        return Err(Error::input(
+            data_args.data.as_deref().unwrap_or_default(),
+            "the data has several districts, pick one with --district or convert it to TOML or wide CSV",
+        ));
     }
 
-    let mut s = cursive::default();
-    let mut table = TableView::<PartyResults, PartyResultsColumn>::new()
-        .column(PartyResultsColumn::Party, "Party", |c| c)
-        .column(PartyResultsColumn::Name, "Name", |c| c)
-        .column(PartyResultsColumn::Votes, "Votes", |c| {
-            c.width(11).align(HAlign::Right)
+    let mut project = Project {
+        name: data.name,
+        date: data.date,
+        method: data.options.method,
+        threshold: data.options.threshold,
+        tie_break: data.options.tie_break,
+        seed: data.options.seed,
+        alliances: data.alliances,
+        ..Default::default()
+    };
+
+    match <[Votes; 1]>::try_from(data.districts) {
+        Ok([votes]) => {
+            project.seats = count.or(votes.seats);
+            project.parties = party_entries(&votes.results);
+            project.blank = votes.blank;
+            project.null = votes.null;
+        }
+        Err(districts) => {
+            project.districts = districts
+                .into_iter()
+                .map(|votes| District {
+                    seats: votes.seats.or(count),
+                    name: votes.district,
+                    data: None,
+                    parties: party_entries(&votes.results),
+                    blank: votes.blank,
+                    null: votes.null,
+                })
+                .collect();
+        }
+    }
+
+    write_output(output_path, &convert(&project, format))
+}
+
+/// Extensions of the files taken from the directories given to `batch`.
+const BATCH_EXTENSIONS: [&str; 7] = ["csv", "tsv", "json", "toml", "xlsx", "xls", "ods"];
+
+/// The files given to `batch`, those in the directories given and those
+/// matching the glob patterns given, in order.
+fn batch_files(paths: &[String]) -> Result<Vec<String>, Error> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if Path::new(path).is_dir() {
+            let entries = fs::read_dir(path).map_err(|source| Error::Io {
+                path: path.clone(),
+                source,
+            })?;
+
+            let mut found: Vec<String> = entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|entry| entry.is_file())
+                .filter(|entry| {
+                    entry.extension().is_some_and(|extension| {
+                        BATCH_EXTENSIONS
+                            .contains(&extension.to_string_lossy().to_lowercase().as_str())
+                    })
+                })
+                .map(|entry| entry.to_string_lossy().into_owned())
+                .collect();
+            found.sort();
+
+            files.extend(found);
+        } else if path.contains(['*', '?', '[']) {
+            let matches = match glob::glob(path) {
+                Ok(matches) => matches,
+                Err(err) => Cli::command()
+                    .error(
+                        clap::error::ErrorKind::InvalidValue,
+                        format!("invalid pattern {path}: {err}"),
+                    )
+                    .exit(),
+            };
+
+            let mut found: Vec<String> = matches
+                .filter_map(Result::ok)
+                .filter(|entry| entry.is_file())
+                .map(|entry| entry.to_string_lossy().into_owned())
+                .collect();
+            found.sort();
+
+            files.extend(found);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    Ok(files)
+}
+
+/// Path to save the report of a file of a batch to, numbered if another
+/// file with the same name already took it.
+fn report_path(
+    reports: &str,
+    path: &str,
+    extension: &str,
+    taken: &mut HashSet<PathBuf>,
+) -> PathBuf {
+    let stem = Path::new(path)
+        .file_stem()
+        .map_or("results".into(), |stem| stem.to_string_lossy().into_owned());
+
+    let mut report = Path::new(reports).join(format!("{stem}.{extension}"));
+    let mut number = 2;
+    while !taken.insert(report.clone()) {
+        report = Path::new(reports).join(format!("{stem}-{number}.{extension}"));
+        number += 1;
+    }
+
+    report
+}
+
+fn run_batch(
+    paths: &[String],
+    read: &ReadArgs,
+    count: Option<u32>,
+    allocation: &AllocationArgs,
+    reports: Option<String>,
+    report: &ReportArgs,
+    output_path: Option<String>,
+) -> Result<(), Error> {
+    let format = report.format(None);
+    let options = report.options()?;
+    let template = report.template()?;
+
+    let files = batch_files(paths)?;
+    if files.is_empty() {
+        return Err(Error::input(
+            &paths.join(" "),
+            "no election files were found",
+        ));
+    }
+
+    if let Some(ref reports) = reports {
+        fs::create_dir_all(reports).map_err(|source| Error::Export {
+            path: reports.clone(),
+            source,
+        })?;
+    }
+
+    let mut taken = HashSet::new();
+    let mut batch = Vec::new();
+
+    for path in files {
+        let data_args = DataArgs {
+            data: Some(path.clone()),
+            tally: None,
+            stations: None,
+            read: read.clone(),
+        };
+
+        let (mut results, warnings) = match load(&data_args, allocation, false) {
+            Ok(data) => (
+                allocate_districts(&data, count, &path),
+                data.anomalies.iter().map(|a| a.to_string()).collect(),
+            ),
+            Err(err) => (Err(err), Vec::new()),
+        };
+
+        if let (Ok(districts), Some(reports)) = (&results, &reports) {
+            let report = match (&template, &districts[..]) {
+                (Some(template), [(district, result)]) => template.render(district, result),
+                (Some(template), districts) => template.render_districts(districts),
+                (None, [(district, result)]) if format == Format::Wide => {
+                    Ok(report::generate_wide(&[(district.clone(), result.clone())]))
+                }
+                (None, [(_, result)]) => Ok(report::generate_with(result, format, &options)),
+                (None, districts) => {
+                    Ok(report::generate_districts_with(districts, format, &options))
+                }
+            };
+            let extension = template
+                .as_ref()
+                .map_or(format.extension(), |template| template.extension());
+
+            let report_path = report_path(reports, &path, extension, &mut taken);
+            let report_path = report_path.to_string_lossy().into_owned();
+
+            if let Err(err) = report.and_then(|report| write_output(Some(report_path), &report)) {
+                results = Err(err);
+            }
+        }
+
+        batch.push(BatchFile {
+            path,
+            results,
+            warnings,
         });
+    }
+
+    write_output(output_path, &batch::generate_summary(&batch))?;
+
+    // Every file has been tried, but the batch as a whole failed
+    if let Some(Err(err)) = batch.iter().map(|f| &f.results).find(|r| r.is_err()) {
+        exit(err.exit_code());
+    }
 
-    table.set_on_submit(edit_party_dialog);
+    Ok(())
+}
+
+fn run_command(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Tui {
+            data,
+            count,
+            allocation,
+        } => run_tui(load(&data, &allocation, true)?, count),
+        Command::Calculate {
+            data,
+            count,
+            allocation,
+            output,
+            report,
+            charts,
+            remaining,
+        } => calculate(
+            load(&data, &allocation, false)?,
+            count,
+            &report,
+            &charts,
+            remaining,
+            output.output,
+            data.data.as_deref().unwrap_or_default(),
+        ),
+        Command::Validate { data, output } => run_validate(&data, output.output),
+        Command::Convert {
+            data,
+            count,
+            allocation,
+            output,
+            to,
+        } => run_convert(&data, count, &allocation, output.output, to),
+        Command::Compare {
+            data: data_args,
+            count,
+            allocation,
+            output,
+            methods,
+        } => {
+            let data = load(&data_args, &allocation, false)?;
+            print_anomalies(&data.anomalies);
+            let (votes, options) = single_district(data, &data_args)?;
+
+            let results = compare::compare(
+                seats_of(&votes, count),
+                &votes.parties(),
+                &options,
+                &methods,
+            )?;
+
+            write_output(output.output, &compare::generate_report(&results))
+        }
+        Command::Simulate {
+            data: data_args,
+            count,
+            allocation,
+            output,
+            swings,
+        } => {
+            let data = load(&data_args, &allocation, false)?;
+            print_anomalies(&data.anomalies);
+            let (votes, options) = single_district(data, &data_args)?;
 
-    s.add_layer(
-        OnEventView::new(
-            Dialog::around(
-                LinearLayout::vertical()
-                    .child(
-                        LinearLayout::horizontal()
-                            .child(TextView::new("Seats to assign: "))
-                            .child(EditView::new().with_name("seat_count").fixed_width(5)),
+            let votes_by_party = dhondt_method::votes_by_party(&votes.parties());
+            let swung = match simulate::apply_swings(&votes_by_party, &swings) {
+                Ok(swung) => swung,
+                Err(party) => Cli::command()
+                    .error(
+                        clap::error::ErrorKind::InvalidValue,
+                        format!("there's no party named {party} in the data"),
                     )
-                    .child(PaddedView::lrtb(
-                        0,
-                        0,
-                        1,
-                        0,
-                        table.with_name("votes_table").min_size((75, 20)),
-                    )),
+                    .exit(),
+            };
+
+            let (before, after) =
+                simulate::simulate(seats_of(&votes, count), &votes_by_party, &swung, &options)?;
+
+            write_output(
+                output.output,
+                &simulate::generate_report(&swings, &before, &after),
             )
-            .title("Party results")
-            .button("Add candidate", new_party_dialog)
-            .button("Calculate", start_calculation)
-            .button("Clear", confirm_clear),
-        )
-        .on_event(Key::Esc, confirm_quit)
-        .on_event(Event::CtrlChar('s'), start_calculation),
-    );
+        }
+        Command::Batch {
+            paths,
+            read,
+            count,
+            allocation,
+            reports,
+            report,
+            output,
+        } => run_batch(&paths, &read, count, &allocation, reports, &report, output),
+        Command::Sweep {
+            data: data_args,
+            allocation,
+            output,
+            seats,
+        } => {
+            let data = load(&data_args, &allocation, false)?;
+            print_anomalies(&data.anomalies);
+            let (votes, options) = single_district(data, &data_args)?;
 
-    if !initial_data.is_empty() {
-        s.call_on_name(
-            "votes_table",
-            move |table: &mut TableView<PartyResults, PartyResultsColumn>| {
-                table.set_items(initial_data);
-            },
-        )
-        .unwrap();
+            let results = sweep::sweep(seats, &votes.parties(), &options)?;
+
+            write_output(output.output, &sweep::generate_report(&results))
+        }
+        Command::Stv {
+            ballots,
+            count,
+            surplus,
+            tie_break,
+            output,
+        } => write_output(
+            output.output,
+            &stv_report(&ballots, count, surplus, tie_break)?,
+        ),
+        Command::Portfolios {
+            portfolios,
+            data,
+            preferences,
+            output,
+        } => run_portfolios(&data, &portfolios, preferences.as_deref(), output.output),
+        Command::Committees {
+            committees,
+            data,
+            method,
+            output,
+        } => run_committees(&data, &committees, method, output.output),
+        #[cfg(feature = "tui")]
+        Command::Watch {
+            tally,
+            stations,
+            count,
+            allocation,
+        } => run_live(count, tally, &stations, &allocation),
+        #[cfg(not(feature = "tui"))]
+        Command::Watch { .. } => no_tui(),
+        Command::Minutes {
+            data: data_args,
+            count,
+            allocation,
+            output,
+            format,
+            locale,
+            election,
+            date,
+            blank,
+            null,
+            signatories,
+        } => {
+            let data = load(&data_args, &allocation, false)?;
+            print_anomalies(&data.anomalies);
+            let election = election.or(data.name.clone());
+            let date = date.or(data.date.map(|date| date.to_string()));
+            let (votes, options) = single_district(data, &data_args)?;
+
+            let result =
+                dhondt_method::calculate_with(seats_of(&votes, count), &votes.parties(), &options)?;
+            let minutes = Minutes {
+                // The name of a single district is the name of the election
+                district: if Some(&votes.district) == election.as_ref() {
+                    String::new()
+                } else {
+                    votes.district
+                },
+                election,
+                date,
+                blank: blank.or(votes.blank).unwrap_or_default(),
+                null: null.or(votes.null).unwrap_or_default(),
+                signatories,
+                tie_break: options.tie_break,
+                seed: options.seed,
+            };
+
+            write_output(
+                output.output,
+                &minutes::generate(
+                    &result,
+                    &minutes,
+                    locale.unwrap_or_else(i18n::locale),
+                    format,
+                ),
+            )
+        }
     }
+}
 
-    if let Some(val) = args.count {
-        s.call_on_name("seat_count", |view: &mut EditView| {
-            view.set_content(val.to_string());
-        });
+fn run(mut args: Args) -> Result<(), Error> {
+    // Without a terminal, results go to stdout as with -o
+    if !args.watch && !is_interactive(&args) {
+        args.output.get_or_insert(None);
+    }
+
+    if args.validate {
+        return run_validate(&args.data, args.output.flatten());
+    }
+
+    if let Some(ref portfolios_path) = args.portfolios {
+        let Some(ref seats_path) = args.data.data else {
+            missing_argument("-d is required when using -p");
+        };
+
+        return run_portfolios(
+            seats_path,
+            portfolios_path,
+            args.preferences.as_deref(),
+            args.output.flatten(),
+        );
+    }
+
+    if let Some(ref committees_path) = args.committees {
+        let Some(ref groups_path) = args.data.data else {
+            missing_argument("-d is required when using --committees");
+        };
+
+        return run_committees(
+            groups_path,
+            committees_path,
+            args.allocation.method.unwrap_or_default(),
+            args.output.flatten(),
+        );
     }
 
-    s.run();
+    if args.stv.is_some() {
+        return run_stv(args);
+    }
+
+    if args.watch {
+        #[cfg(feature = "tui")]
+        return run_live(
+            args.count.unwrap(),
+            args.data.tally.unwrap(),
+            &args.data.stations.unwrap(),
+            &args.allocation,
+        );
+
+        #[cfg(not(feature = "tui"))]
+        no_tui();
+    }
+
+    let Some(output_path) = args.output else {
+        return run_tui(load(&args.data, &args.allocation, true)?, args.count);
+    };
+
+    if args.data.data.is_none() && args.data.tally.is_none() {
+        missing_argument(
+            "-c and -d (or -t) are required when using -o or running without a terminal",
+        );
+    }
+
+    calculate(
+        load(&args.data, &args.allocation, false)?,
+        args.count,
+        &args.report,
+        &args.charts,
+        args.remaining,
+        output_path,
+        args.data.data.as_deref().unwrap_or_default(),
+    )
+}
+
+/// Rejects the options of the UI when a command is given. Unlike clap's
+/// `args_conflicts_with_subcommands`, this lets `--lang` go before the
+/// command.
+fn check_subcommand_conflicts(matches: &ArgMatches) {
+    let Some((name, _)) = matches.subcommand() else {
+        return;
+    };
+
+    let mut command = Cli::command();
+    command.build();
+    let conflict = command.get_arguments().find(|arg| {
+        arg.get_id() != "lang"
+            && matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+    });
+
+    if let Some(arg) = conflict {
+        let message = format!("the subcommand '{name}' cannot be used with '{arg}'");
+
+        command
+            .error(clap::error::ErrorKind::ArgumentConflict, message)
+            .exit();
+    }
+}
+
+fn main() {
+    let matches = Cli::command().get_matches();
+    check_subcommand_conflicts(&matches);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    i18n::set_locale(cli.lang.unwrap_or_else(Locale::from_env));
+
+    let result = match cli.command {
+        Some(command) => run_command(command),
+        None => run(cli.args),
+    };
+
+    if let Err(err) = result {
+        fail(err);
+    }
 }
@@ -0,0 +1,174 @@
+use crate::{
+    apportionment::Method,
+    election::{AllocationResult, Tie},
+    i18n::{locale, t, t_with},
+    numeric::{format_decimal, format_num, format_percent},
+    report::{quotient_table, ReportOptions},
+};
+
+/// Width of the seat bar of a party winning every seat.
+const BAR_WIDTH: u32 = 20;
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+fn row(cells: &[String]) -> String {
+    format!("| {} |\n", cells.join(" | "))
+}
+
+fn bar(seats: u32, seat_count: u32) -> String {
+    "█".repeat((seats * BAR_WIDTH).div_ceil(seat_count) as usize)
+}
+
+fn generate_result(output: &mut String, result: &AllocationResult, options: &ReportOptions) {
+    output.push_str(&format!(
+        "{} **{}**  \n",
+        t("Seats to assign:"),
+        result.seat_count
+    ));
+    if result.method != Method::Dhondt {
+        output.push_str(&format!("{} {}  \n", t("Method:"), result.method.name()));
+    }
+    if let Some(threshold) = result.threshold {
+        output.push_str(&format!(
+            "{} {}  \n",
+            t("Threshold:"),
+            locale().percent(threshold, None)
+        ));
+    }
+    output.push_str(&format!(
+        "{} {}\n\n",
+        t("Total of votes:"),
+        format_num(result.total_votes)
+    ));
+
+    output.push_str(&format!(
+        "| {} | {} | {} | {} | |\n",
+        t("Party"),
+        t("Votes"),
+        t("Share"),
+        t("Seats")
+    ));
+    output.push_str("| --- | ---: | ---: | ---: | --- |\n");
+    for party in &result.parties {
+        output.push_str(&row(&[
+            escape(&party.name),
+            format_num(party.votes),
+            format_percent(party.share, 2),
+            if party.below_threshold {
+                "-".into()
+            } else {
+                party.seats.to_string()
+            },
+            bar(party.seats, result.seat_count),
+        ]));
+    }
+
+    if result.has_candidates() {
+        output.push_str(&format!("\n#### {}\n\n", t("Candidates")));
+        output.push_str(&row(&[
+            t("Party").into(),
+            t("Candidate").into(),
+            t("Votes").into(),
+            t("Share").into(),
+            t("Elected").into(),
+        ]));
+        output.push_str("| --- | --- | ---: | ---: | :---: |\n");
+
+        for party in &result.parties {
+            for candidate in &party.elected {
+                output.push_str(&row(&[
+                    escape(&party.name),
+                    format!("**{}**", escape(&candidate.name)),
+                    format_num(candidate.votes),
+                    format_percent(candidate.share, 2),
+                    "✓".into(),
+                ]));
+            }
+
+            for candidate in &party.not_elected {
+                output.push_str(&row(&[
+                    escape(&party.name),
+                    escape(&candidate.name),
+                    format_num(candidate.votes),
+                    format_percent(candidate.share, 2),
+                    if result.is_tied(&party.name, &candidate.name) {
+                        "?".into()
+                    } else {
+                        String::new()
+                    },
+                ]));
+            }
+        }
+    }
+
+    if let Some(table) = quotient_table(result).filter(|_| options.quotients) {
+        let columns = table.first().map_or(0, |(_, quotients)| quotients.len());
+
+        output.push_str(&format!("\n#### {}\n\n", t("Quotients")));
+        output.push_str(&row(&std::iter::once(t("Party").to_string())
+            .chain((1..=columns).map(|seat| seat.to_string()))
+            .collect::<Vec<_>>()));
+        output.push_str(&row(&std::iter::once("---".to_string())
+            .chain(std::iter::repeat_n("---:".to_string(), columns))
+            .collect::<Vec<_>>()));
+
+        for (party, quotients) in table {
+            output.push_str(&row(&std::iter::once(escape(party))
+                .chain(quotients.into_iter().map(|(value, won)| {
+                    let value = format_decimal(value, 2);
+                    if won {
+                        format!("**{value}**")
+                    } else {
+                        value
+                    }
+                }))
+                .collect::<Vec<_>>()));
+        }
+    }
+
+    let notes: Vec<String> = result
+        .ties
+        .iter()
+        .filter_map(|tie| match tie {
+            Tie::Seat { seat, parties } => Some(t_with(
+                "Seat {seat} was tied between {parties}",
+                &[("seat", seat), ("parties", &escape(&parties.join(", ")))],
+            )),
+            Tie::Candidates { .. } => None,
+        })
+        .chain(
+            result
+                .warnings
+                .iter()
+                .map(|warning| format!("{} {}", t("Warning:"), escape(warning))),
+        )
+        .collect();
+
+    if !notes.is_empty() {
+        output.push('\n');
+        for note in notes {
+            output.push_str(&format!("> {note}  \n"));
+        }
+    }
+}
+
+/// Report with GitHub-flavoured Markdown tables, with a section for every
+/// district (a single result has no district name).
+pub fn generate(results: &[(String, AllocationResult)], options: &ReportOptions) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("# {}\n", t("Seat distribution")));
+
+    for (district, result) in results {
+        output.push('\n');
+        if !district.is_empty() {
+            output.push_str(&format!("## {}\n\n", escape(district)));
+        }
+
+        generate_result(&mut output, result, options);
+    }
+
+    output
+}
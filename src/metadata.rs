@@ -0,0 +1,56 @@
+use crate::election::{AllocationResult, PartyAllocation};
+
+/// Colours given to parties without one, in order, repeating after the last
+/// one.
+const PALETTE: [&str; 10] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+    "#bcbd22", "#7f7f7f",
+];
+
+#[derive(Clone, Debug)]
+pub struct PartyMetadata {
+    pub party: String,
+    /// CSS colour (`#rrggbb`, `#rgb` or a name)
+    pub colour: Option<String>,
+}
+
+/// How parties are shown in charts and reports: their colours, and the
+/// order they are drawn in (left to right in a hemicycle). Parties that
+/// aren't listed go after the listed ones, in the order of the results.
+#[derive(Clone, Debug, Default)]
+pub struct Metadata {
+    pub parties: Vec<PartyMetadata>,
+}
+
+/// Whether a colour can be used as is in CSS and SVG.
+pub fn is_valid_colour(colour: &str) -> bool {
+    match colour.strip_prefix('#') {
+        Some(hex) => matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !colour.is_empty() && colour.chars().all(|c| c.is_ascii_alphabetic()),
+    }
+}
+
+impl Metadata {
+    fn position(&self, party: &str) -> Option<usize> {
+        self.parties.iter().position(|p| p.party == party)
+    }
+
+    /// Parties of the results in display order.
+    pub fn order<'a>(&self, result: &'a AllocationResult) -> Vec<&'a PartyAllocation> {
+        let mut parties: Vec<&PartyAllocation> = result.parties.iter().collect();
+        // The sort is stable, so unlisted parties keep the order of the results
+        parties.sort_by_key(|p| self.position(&p.name).unwrap_or(usize::MAX));
+
+        parties
+    }
+
+    /// Colour of a party: the one given for it, or a colour of the palette
+    /// picked by `index`. Callers pass the position of the party among all
+    /// the parties of the results, shown or not, so that it keeps its colour
+    /// from one table or chart to the next.
+    pub fn colour(&self, party: &str, index: usize) -> String {
+        self.position(party)
+            .and_then(|i| self.parties[i].colour.clone())
+            .unwrap_or_else(|| PALETTE[index % PALETTE.len()].into())
+    }
+}
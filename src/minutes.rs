@@ -0,0 +1,409 @@
+use crate::{
+    dhondt::TieBreak,
+    election::{AllocationResult, Tie},
+    html::escape,
+    i18n::{fill_in, Locale},
+    report::quotient_table,
+    text_table::format_table,
+};
+
+/// Format of the minutes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Format {
+    /// Plain text, ready to print
+    #[default]
+    Text,
+    /// Self-contained HTML page
+    Html,
+}
+
+/// Details of the minutes that the allocation result doesn't have.
+#[derive(Clone, Debug, Default)]
+pub struct Minutes {
+    pub election: Option<String>,
+    pub date: Option<String>,
+    pub district: String,
+    pub blank: u32,
+    pub null: u32,
+    /// Who signs the minutes (the president and the secretary if none)
+    pub signatories: Vec<String>,
+    /// How ties between parties with the same votes were resolved
+    pub tie_break: TieBreak,
+    pub seed: u64,
+}
+
+/// Wording of the minutes in a language, which can differ from the one of
+/// the rest of the reports.
+#[derive(Clone, Copy)]
+struct Wording(Locale);
+
+impl Wording {
+    fn t(&self, message: &'static str) -> &'static str {
+        self.0.translate_in("minutes", message)
+    }
+
+    fn number(&self, value: f64, decimals: usize) -> String {
+        self.0.number(value, Some(decimals))
+    }
+
+    fn votes(&self, votes: u32) -> String {
+        self.number(votes as f64, 0)
+    }
+}
+
+/// A part of the minutes, written the same way in every format.
+enum Block {
+    Heading(String),
+    Fields(Vec<(&'static str, String)>),
+    /// Rows of a table (the first one is the header), with the columns
+    /// aligned to the left
+    Table(Vec<Vec<String>>, Vec<bool>),
+    Paragraph(String),
+    Signatures(Vec<String>),
+}
+
+/// How a seat tie was resolved: by votes if the winner had more than the
+/// other parties, or else by the tie break of the allocation.
+fn tie_rule(
+    wording: Wording,
+    result: &AllocationResult,
+    minutes: &Minutes,
+    seat: u32,
+    parties: &[String],
+) -> String {
+    let votes = |party: &str| result.party(party).map_or(0, |p| p.votes);
+    let winner = result
+        .seats
+        .get(seat as usize - 1)
+        .map_or("", |s| s.party.as_str());
+
+    if parties
+        .iter()
+        .filter(|p| p.as_str() != winner)
+        .all(|p| votes(p) < votes(winner))
+    {
+        return wording.t("having more votes").into();
+    }
+
+    match minutes.tie_break {
+        TieBreak::MostVotes => wording.t("alphabetical order").into(),
+        TieBreak::Lot => fill_in(
+            wording.t("drawing of lots (seed {seed})"),
+            &[("seed", &minutes.seed)],
+        ),
+    }
+}
+
+fn blocks(result: &AllocationResult, minutes: &Minutes, wording: Wording) -> Vec<Block> {
+    let mut blocks = vec![Block::Heading(
+        wording
+            .t("Certificate of proclamation of elected candidates")
+            .into(),
+    )];
+
+    let mut fields = Vec::new();
+    if let Some(ref election) = minutes.election {
+        fields.push((wording.t("Election"), election.clone()));
+    }
+    if let Some(ref date) = minutes.date {
+        fields.push((wording.t("Date"), date.clone()));
+    }
+    if !minutes.district.is_empty() {
+        fields.push((wording.t("District"), minutes.district.clone()));
+    }
+    fields.push((wording.t("Seats"), result.seat_count.to_string()));
+    fields.push((wording.t("Method"), result.method.name().into()));
+    if let Some(threshold) = result.threshold {
+        fields.push((wording.t("Threshold"), wording.0.percent(threshold, None)));
+    }
+    blocks.push(Block::Fields(fields));
+
+    let valid = result.total_votes as u64 + minutes.blank as u64;
+    blocks.push(Block::Heading(wording.t("Votes").into()));
+    blocks.push(Block::Fields(vec![
+        (
+            wording.t("Votes cast"),
+            wording.number((valid + minutes.null as u64) as f64, 0),
+        ),
+        (wording.t("Null votes"), wording.votes(minutes.null)),
+        (wording.t("Valid votes"), wording.number(valid as f64, 0)),
+        (wording.t("Blank votes"), wording.votes(minutes.blank)),
+        (
+            wording.t("Votes for candidacies"),
+            wording.votes(result.total_votes),
+        ),
+    ]));
+
+    let mut lines = vec![vec![
+        wording.t("Party").to_string(),
+        wording.t("Votes").into(),
+        wording.t("Share").into(),
+        wording.t("Seats").into(),
+    ]];
+    for party in &result.parties {
+        lines.push(vec![
+            party.name.clone(),
+            wording.votes(party.votes),
+            wording.0.percent(party.share, Some(2)),
+            if party.below_threshold {
+                "-".into()
+            } else {
+                party.seats.to_string()
+            },
+        ]);
+    }
+    blocks.push(Block::Heading(wording.t("Results").into()));
+    blocks.push(Block::Table(lines, vec![true, false, false, false]));
+
+    blocks.push(Block::Heading(wording.t("Quotients").into()));
+    match quotient_table(result) {
+        Some(table) => {
+            let columns = table.first().map_or(0, |(_, q)| q.len());
+
+            let mut lines = vec![std::iter::once(wording.t("Party").to_string())
+                .chain((1..=columns).map(|seat| seat.to_string()))
+                .collect::<Vec<_>>()];
+            for (party, quotients) in table {
+                lines.push(
+                    std::iter::once(party.to_string())
+                        .chain(quotients.into_iter().map(|(value, won)| {
+                            let value = wording.number(value, 2);
+                            if won {
+                                format!("{value}*")
+                            } else {
+                                value
+                            }
+                        }))
+                        .collect(),
+                );
+            }
+
+            let mut left_align = vec![false; columns + 1];
+            left_align[0] = true;
+
+            blocks.push(Block::Table(lines, left_align));
+            blocks.push(Block::Paragraph(
+                wording
+                    .t("Quotients that won a seat are marked with *.")
+                    .into(),
+            ));
+        }
+        None => blocks.push(Block::Paragraph(
+            wording
+                .t("The largest remainder method has no quotients.")
+                .into(),
+        )),
+    }
+
+    // Every party's candidates take its seats in the order they were elected
+    let has_quotients = result.seats.iter().any(|s| s.quotient.is_some());
+    let mut header = vec![
+        wording.t("Seat").to_string(),
+        wording.t("Party").into(),
+        wording.t("Candidate").into(),
+    ];
+    if has_quotients {
+        header.push(wording.t("Quotient").into());
+    }
+    let mut lines = vec![header];
+    let mut taken: Vec<(&str, usize)> = Vec::new();
+    for (i, seat) in result.seats.iter().enumerate() {
+        let position = match taken.iter_mut().find(|(p, _)| *p == seat.party) {
+            Some((_, count)) => {
+                *count += 1;
+                *count - 1
+            }
+            None => {
+                taken.push((&seat.party, 1));
+                0
+            }
+        };
+        let candidate = result
+            .party(&seat.party)
+            .and_then(|p| p.elected.get(position))
+            .map_or(String::new(), |c| c.name.clone());
+
+        let mut line = vec![(i + 1).to_string(), seat.party.clone(), candidate];
+        line.extend(seat.quotient.map(|q| wording.number(q, 2)));
+
+        lines.push(line);
+    }
+    blocks.push(Block::Heading(wording.t("Elected candidates").into()));
+    blocks.push(Block::Table(lines, vec![false, true, true, false]));
+
+    blocks.push(Block::Heading(wording.t("Ties").into()));
+    if result.ties.is_empty() {
+        blocks.push(Block::Paragraph(wording.t("There were no ties.").into()));
+    }
+    for tie in &result.ties {
+        blocks.push(Block::Paragraph(match tie {
+            Tie::Seat { seat, parties } => fill_in(
+                wording.t("Seat {seat} was tied between {parties}, and went to {winner} by {rule}."),
+                &[
+                    ("seat", seat),
+                    ("parties", &parties.join(", ")),
+                    (
+                        "winner",
+                        &result
+                            .seats
+                            .get(*seat as usize - 1)
+                            .map_or("", |s| s.party.as_str()),
+                    ),
+                    ("rule", &tie_rule(wording, result, minutes, *seat, parties)),
+                ],
+            ),
+            Tie::Candidates { party, candidates } => fill_in(
+                wording.t("Candidates {candidates} of {party} were tied for the last elected position, which was resolved by alphabetical order."),
+                &[("candidates", &candidates.join(", ")), ("party", party)],
+            ),
+        }));
+    }
+
+    blocks.push(Block::Heading(wording.t("Signatures").into()));
+    blocks.push(Block::Paragraph(
+        wording
+            .t("In witness whereof, these minutes are signed by the undersigned.")
+            .into(),
+    ));
+    blocks.push(Block::Signatures(if minutes.signatories.is_empty() {
+        ["President", "Secretary"]
+            .iter()
+            .map(|s| wording.t(s).to_string())
+            .collect()
+    } else {
+        minutes.signatories.clone()
+    }));
+
+    blocks
+}
+
+fn generate_text(blocks: &[Block]) -> String {
+    let mut output = String::new();
+
+    for (i, block) in blocks.iter().enumerate() {
+        match block {
+            Block::Heading(heading) if i == 0 => {
+                output.push_str(&format!(
+                    "{}\n{}\n",
+                    heading.to_uppercase(),
+                    "=".repeat(heading.chars().count())
+                ));
+            }
+            Block::Heading(heading) => {
+                output.push_str(&format!(
+                    "\n{heading}\n{}\n",
+                    "-".repeat(heading.chars().count())
+                ));
+            }
+            Block::Fields(fields) => {
+                let lines: Vec<Vec<String>> = fields
+                    .iter()
+                    .map(|(label, value)| vec![format!("{label}:"), value.clone()])
+                    .collect();
+
+                output.push_str(&format_table(&lines, &[true, true]));
+            }
+            Block::Table(lines, left_align) => output.push_str(&format_table(lines, left_align)),
+            Block::Paragraph(text) => output.push_str(&format!("{text}\n")),
+            Block::Signatures(signatories) => {
+                for signatory in signatories {
+                    output.push_str(&format!(
+                        "\n\n\n______________________________\n{signatory}\n"
+                    ));
+                }
+            }
+        }
+    }
+
+    output
+}
+
+const STYLE: &str = "
+body { font-family: Georgia, serif; margin: 2em auto; max-width: 50em; color: #111; }
+h1 { text-align: center; font-size: 1.5em; }
+table { border-collapse: collapse; margin: 0.5em 0 1em; }
+th, td { padding: 0.2em 0.8em; border: 1px solid #999; }
+table.fields th, table.fields td { border: none; text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+.signatures { display: flex; justify-content: space-around; margin-top: 4em; }
+.signature { border-top: 1px solid #111; min-width: 14em; padding-top: 0.3em; text-align: center; }
+";
+
+fn generate_html(blocks: &[Block]) -> String {
+    let mut output = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+
+    if let Some(Block::Heading(title)) = blocks.first() {
+        output.push_str(&format!("<title>{}</title>\n", escape(title)));
+    }
+    output.push_str(&format!("<style>{STYLE}</style>\n</head>\n<body>\n"));
+
+    for (i, block) in blocks.iter().enumerate() {
+        match block {
+            Block::Heading(heading) => {
+                let level = if i == 0 { 1 } else { 2 };
+                output.push_str(&format!("<h{level}>{}</h{level}>\n", escape(heading)));
+            }
+            Block::Fields(fields) => {
+                output.push_str("<table class=\"fields\">\n");
+                for (label, value) in fields {
+                    output.push_str(&format!(
+                        "<tr><th>{}</th><td>{}</td></tr>\n",
+                        escape(label),
+                        escape(value)
+                    ));
+                }
+                output.push_str("</table>\n");
+            }
+            Block::Table(lines, left_align) => {
+                output.push_str("<table>\n");
+                for (row, line) in lines.iter().enumerate() {
+                    output.push_str("<tr>");
+                    for (cell, &left) in line.iter().zip(left_align) {
+                        let cell = escape(cell);
+                        match (row, left) {
+                            (0, _) => output.push_str(&format!("<th>{cell}</th>")),
+                            (_, true) => output.push_str(&format!("<td>{cell}</td>")),
+                            (_, false) => {
+                                output.push_str(&format!("<td class=\"num\">{cell}</td>"))
+                            }
+                        }
+                    }
+                    output.push_str("</tr>\n");
+                }
+                output.push_str("</table>\n");
+            }
+            Block::Paragraph(text) => output.push_str(&format!("<p>{}</p>\n", escape(text))),
+            Block::Signatures(signatories) => {
+                output.push_str("<div class=\"signatures\">\n");
+                for signatory in signatories {
+                    output.push_str(&format!(
+                        "<div class=\"signature\">{}</div>\n",
+                        escape(signatory)
+                    ));
+                }
+                output.push_str("</div>\n");
+            }
+        }
+    }
+
+    output.push_str("</body>\n</html>\n");
+
+    output
+}
+
+/// Official minutes of the proclamation of the elected candidates of a
+/// district, in the wording of `locale`.
+pub fn generate(
+    result: &AllocationResult,
+    minutes: &Minutes,
+    locale: Locale,
+    format: Format,
+) -> String {
+    let blocks = blocks(result, minutes, Wording(locale));
+
+    match format {
+        Format::Text => generate_text(&blocks),
+        Format::Html => generate_html(&blocks),
+    }
+}
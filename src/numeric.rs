@@ -1,10 +1,183 @@
-pub fn format_num(num: u32) -> String {
-    num.to_string()
+use crate::i18n::locale;
+
+/// Groups the thousands of a string of digits with `separator`.
+pub(crate) fn group_digits(digits: &str, separator: &str) -> String {
+    digits
         .as_bytes()
         .rchunks(3)
         .rev()
         .map(std::str::from_utf8)
         .collect::<Result<Vec<&str>, _>>()
         .unwrap()
-        .join(" ")
+        .join(separator)
+}
+
+/// Formats a whole number with the thousands separator of the language of
+/// the reports.
+pub fn format_num(num: u32) -> String {
+    locale().number(num as f64, Some(0))
+}
+
+/// Formats a non-negative number with the given decimal places, with the
+/// separators of the language of the reports.
+pub fn format_decimal(value: f64, decimals: usize) -> String {
+    locale().number(value, Some(decimals))
+}
+
+/// Formats a percentage with the given decimal places, as the language of
+/// the reports writes them.
+pub fn format_percent(value: f64, decimals: usize) -> String {
+    locale().percent(value, Some(decimals))
+}
+
+/// Formats a non-negative number with the given decimal places and
+/// `decimal` mark, grouping the thousands of its integer part by three with
+/// `separator` (or not at all without one).
+pub fn format_grouped(
+    value: f64,
+    decimals: usize,
+    decimal: &str,
+    separator: Option<&str>,
+) -> String {
+    let text = format!("{value:.decimals$}");
+    let group = |integer: &str| match separator {
+        Some(separator) => group_digits(integer, separator),
+        None => integer.to_string(),
+    };
+
+    match text.split_once('.') {
+        Some((integer, fraction)) => format!("{}{decimal}{fraction}", group(integer)),
+        None => group(&text),
+    }
+}
+
+/// How numbers are written in the tables of papers (LaTeX and Typst).
+#[derive(Clone, Copy, Debug)]
+pub struct NumberStyle {
+    /// Decimal places of shares and quotients
+    pub decimals: usize,
+    /// Whether to group the thousands (with a thin space)
+    pub grouping: bool,
+}
+
+impl Default for NumberStyle {
+    fn default() -> Self {
+        NumberStyle {
+            decimals: 2,
+            grouping: true,
+        }
+    }
+}
+
+/// How numbers are written in imported files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum NumberFormat {
+    /// Any of the formats below, as long as thousands are grouped by three
+    #[default]
+    Auto,
+    /// Digits only
+    Plain,
+    /// Thousands separated by dots or spaces, decimals by a comma (1.340,00)
+    DecimalComma,
+    /// Thousands separated by commas or spaces, decimals by a dot (1,340.00)
+    DecimalPoint,
+}
+
+/// Parses a vote count, allowing thousands separators and a zero decimal
+/// part as written in the given format.
+pub fn parse_num(text: &str, format: NumberFormat) -> Option<u32> {
+    let text = text.trim();
+
+    if format == NumberFormat::Plain {
+        return text.parse().ok();
+    }
+
+    // Spaces (including non-breaking ones) and apostrophes only ever group
+    // thousands
+    let text: String = text
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '\u{a0}' | '\u{202f}' | '\''))
+        .collect();
+
+    let (group, decimal) = match format {
+        NumberFormat::DecimalComma => ('.', ','),
+        NumberFormat::DecimalPoint => (',', '.'),
+        _ => match (text.find('.'), text.find(',')) {
+            // The last separator is the decimal one when both appear
+            (Some(dot), Some(comma)) if dot < comma => ('.', ','),
+            (Some(_), Some(_)) => (',', '.'),
+            (Some(_), None) => ('.', ','),
+            _ => (',', '.'),
+        },
+    };
+
+    let (integer, fraction) = match text.split_once(decimal) {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (text.as_str(), None),
+    };
+
+    if fraction.is_some_and(|f| f.is_empty() || f.chars().any(|c| c != '0')) {
+        return None;
+    }
+
+    let mut groups = integer.split(group);
+    let first = groups.next()?;
+    let rest: Vec<&str> = groups.collect();
+
+    if !rest.is_empty()
+        && (first.is_empty() || first.len() > 3 || rest.iter().any(|g| g.len() != 3))
+    {
+        return None;
+    }
+
+    std::iter::once(first)
+        .chain(rest)
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        use NumberFormat::*;
+
+        let cases = [
+            ("1340", Auto, Some(1340)),
+            (" 1340 ", Auto, Some(1340)),
+            ("1.340", Auto, Some(1340)),
+            ("1,340", Auto, Some(1340)),
+            ("1 340", Auto, Some(1340)),
+            ("1\u{a0}340", Auto, Some(1340)),
+            ("1'340", Auto, Some(1340)),
+            ("1,340.00", Auto, Some(1340)),
+            ("1.340,00", Auto, Some(1340)),
+            ("12.345.678", Auto, Some(12_345_678)),
+            ("1.5", Auto, None),
+            ("1,5", Auto, None),
+            ("1,340.50", Auto, None),
+            ("1.34", Auto, None),
+            ("1234.567", Auto, None),
+            ("1.", Auto, None),
+            ("", Auto, None),
+            ("-5", Auto, None),
+            ("4294967296", Auto, None),
+            ("1.340", Plain, None),
+            ("1340", Plain, Some(1340)),
+            ("1.340", DecimalComma, Some(1340)),
+            ("1.340", DecimalPoint, None),
+            ("1,340", DecimalComma, None),
+            ("12.0", DecimalPoint, Some(12)),
+            ("12,0", DecimalComma, Some(12)),
+            ("1 340,00", DecimalComma, Some(1340)),
+        ];
+
+        for (text, format, expected) in cases {
+            assert_eq!(parse_num(text, format), expected, "{text:?} as {format:?}");
+        }
+    }
 }
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use crate::{
+    dhondt::{quotient, DHondtError, Quotient},
+    i18n::t,
+    text_table::format_table,
+};
+
+pub struct Pick {
+    pub party: String,
+    pub quotient: Quotient,
+    pub portfolio: String,
+}
+
+/// Runs the sequential picking procedure used to allocate ministries or
+/// committee chairs: the party with the highest quotient picks its most
+/// preferred portfolio still available (or the next one in list order if it
+/// has no preferences left), then quotients are updated.
+pub fn allocate(
+    parties: &[(String, u32)],
+    portfolios: &[String],
+    preferences: &HashMap<String, Vec<String>>,
+) -> Result<Vec<Pick>, DHondtError> {
+    if portfolios.is_empty() {
+        return Err(DHondtError::NoPortfolios);
+    }
+
+    if parties.is_empty() {
+        return Err(DHondtError::NoParties);
+    }
+
+    if parties.iter().all(|p| p.1 == 0) {
+        return Err(DHondtError::NoVotes);
+    }
+
+    let mut picks_by_party: HashMap<&str, u32> = HashMap::new();
+    let mut available: Vec<&String> = portfolios.iter().collect();
+    let mut picks = Vec::new();
+
+    while !available.is_empty() {
+        // Ties go to the party with more seats, then to the one listed first
+        let (party, seats) = parties
+            .iter()
+            .rev()
+            .max_by(|a, b| {
+                let a_quotient = quotient(a.1, *picks_by_party.get(a.0.as_str()).unwrap_or(&0));
+                let b_quotient = quotient(b.1, *picks_by_party.get(b.0.as_str()).unwrap_or(&0));
+
+                a_quotient.cmp(&b_quotient).then(a.1.cmp(&b.1))
+            })
+            .unwrap();
+
+        let party_picks = picks_by_party.entry(party.as_str()).or_default();
+
+        let index = preferences
+            .get(party)
+            .and_then(|prefs| {
+                prefs
+                    .iter()
+                    .find_map(|pref| available.iter().position(|p| *p == pref))
+            })
+            .unwrap_or(0);
+
+        picks.push(Pick {
+            party: party.clone(),
+            quotient: quotient(*seats, *party_picks),
+            portfolio: available.remove(index).clone(),
+        });
+
+        *party_picks += 1;
+    }
+
+    Ok(picks)
+}
+
+pub fn generate_log(parties: &[(String, u32)], picks: &[Pick]) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{} {}\n\n",
+        t("Portfolios to allocate:"),
+        picks.len()
+    ));
+
+    let mut lines = vec![vec![
+        t("Pick").into(),
+        t("Party").into(),
+        t("Quotient").into(),
+        t("Portfolio").into(),
+    ]];
+
+    for (i, pick) in picks.iter().enumerate() {
+        lines.push(vec![
+            format!("{}", i + 1),
+            pick.party.clone(),
+            format!("{:.2}", pick.quotient.value()),
+            pick.portfolio.clone(),
+        ]);
+    }
+
+    output.push_str(&format_table(&lines, &[false, true, false, true]));
+    output.push('\n');
+
+    let mut lines = vec![vec![
+        t("Party").into(),
+        t("Seats").into(),
+        t("Portfolios").into(),
+    ]];
+
+    for (party, seats) in parties {
+        let count = picks.iter().filter(|p| &p.party == party).count();
+        lines.push(vec![party.clone(), seats.to_string(), count.to_string()]);
+    }
+
+    output.push_str(&format_table(&lines, &[true, false, false]));
+
+    output
+}
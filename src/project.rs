@@ -0,0 +1,170 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use toml::value::Datetime;
+
+use crate::{
+    apportionment::Method,
+    csv_parser::parse_file,
+    dhondt::{Options, TieBreak},
+    election::{party_entries, Election, PartyEntry, PartyResults},
+    error::Error,
+    input,
+    json::parse_election,
+};
+
+/// An election file, describing everything about a contest so that it can
+/// be rerun from version control. A single district can be given with the
+/// top-level `seats`, `data` and `parties`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Project {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<Datetime>,
+    #[serde(default)]
+    pub method: Method,
+    /// Percentage of the total votes a party needs to get any seats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+    #[serde(default)]
+    pub tie_break: TieBreak,
+    /// Seed used when ties are broken by lot
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seats: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alliances: Vec<Alliance>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub districts: Vec<District>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parties: Vec<PartyEntry>,
+    /// Blank and null votes of the single district, for the minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blank: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub null: Option<u32>,
+}
+
+/// Parties running together, whose votes count as a single list.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Alliance {
+    pub name: String,
+    pub parties: Vec<String>,
+}
+
+/// A constituency with its own seats and votes, given either as a path to a
+/// CSV or JSON file (relative to the election file) or inline.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct District {
+    pub name: String,
+    pub seats: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parties: Vec<PartyEntry>,
+    /// Blank and null votes, for the minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blank: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub null: Option<u32>,
+}
+
+pub fn parse_project(path: &str) -> Result<Project, Error> {
+    let contents = input::read_to_string(path)?;
+
+    toml::from_str(&contents).map_err(|err| Error::toml(path, &contents, err))
+}
+
+impl Project {
+    pub fn options(&self) -> Options {
+        Options {
+            method: self.method,
+            threshold: self.threshold,
+            tie_break: self.tie_break,
+            seed: self.seed,
+        }
+    }
+
+    /// The districts of the election, or a single one made of the top-level
+    /// seats and votes when there are none.
+    pub fn districts(&self) -> Vec<District> {
+        if !self.districts.is_empty() {
+            return self.districts.clone();
+        }
+
+        vec![District {
+            name: self.name.clone().unwrap_or_default(),
+            seats: self.seats,
+            data: self.data.clone(),
+            parties: self.parties.clone(),
+            blank: self.blank,
+            null: self.null,
+        }]
+    }
+
+    /// Reads the votes of a district, with the parties of every alliance
+    /// still apart. `path` is the election file the data paths are relative
+    /// to.
+    pub fn read_votes(&self, path: &str, district: &District) -> Result<Vec<PartyResults>, Error> {
+        let mut results = Election {
+            parties: district.parties.clone(),
+            ..Default::default()
+        }
+        .results();
+
+        if let Some(ref data) = district.data {
+            let data_path = Path::new(path).parent().unwrap_or(Path::new("")).join(data);
+            let data_path = data_path.to_string_lossy();
+
+            results.extend(if data.ends_with(".json") {
+                parse_election(&data_path)?.results()
+            } else {
+                parse_file(&data_path)?
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+/// Joins the votes of the parties of every alliance under its name.
+pub fn join_alliances(alliances: &[Alliance], results: &mut [PartyResults]) {
+    for result in results {
+        let Some(alliance) = alliances.iter().find(|a| a.parties.contains(&result.party)) else {
+            continue;
+        };
+
+        // Party-only votes keep the party's name within the alliance
+        if result.name.is_empty() {
+            result.name = result.party.clone();
+        }
+        result.party = alliance.name.clone();
+    }
+}
+
+/// Builds a single-district election file with inline votes, as saved from
+/// the UI.
+pub fn generate_project(
+    seat_count: Option<u32>,
+    options: &Options,
+    results: &[PartyResults],
+) -> String {
+    let project = Project {
+        method: options.method,
+        threshold: options.threshold,
+        tie_break: options.tie_break,
+        seed: options.seed,
+        seats: seat_count,
+        parties: party_entries(results),
+        ..Default::default()
+    };
+
+    toml::to_string(&project).expect("election files are always serializable")
+}
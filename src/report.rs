@@ -0,0 +1,227 @@
+use std::{fs::File, io::Write};
+
+use crate::{
+    election::AllocationResult, html, latex, markdown, metadata::Metadata, numeric::NumberStyle,
+    template::Template, typst,
+};
+
+/// Format of the results written with `-o`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Format {
+    /// Aligned text table
+    #[default]
+    Text,
+    /// The structured allocation result
+    Json,
+    /// CSV with the seats of every party as columns (one row per district)
+    Wide,
+    /// Markdown with GitHub tables
+    Markdown,
+    /// Self-contained HTML page
+    Html,
+    /// LaTeX tables (with booktabs)
+    Latex,
+    /// Typst tables
+    Typst,
+}
+
+impl Format {
+    /// The format matching the extension of a file, if any.
+    pub fn from_path(path: &str) -> Option<Format> {
+        let path = path.to_lowercase();
+
+        match path.rsplit_once('.')?.1 {
+            "txt" => Some(Format::Text),
+            "md" | "markdown" => Some(Format::Markdown),
+            "html" | "htm" => Some(Format::Html),
+            "tex" => Some(Format::Latex),
+            "typ" => Some(Format::Typst),
+            _ => None,
+        }
+    }
+
+    /// Extension of the files saved in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Json => "json",
+            Format::Wide => "csv",
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Latex => "tex",
+            Format::Typst => "typ",
+        }
+    }
+}
+
+/// Optional parts of the reports, for the formats that have them.
+#[derive(Clone, Debug, Default)]
+pub struct ReportOptions {
+    /// Include the table of quotients (only for highest-averages methods)
+    pub quotients: bool,
+    /// Colours of the parties
+    pub metadata: Metadata,
+    /// Decimals and grouping of the numbers in LaTeX and Typst tables
+    pub numbers: NumberStyle,
+}
+
+pub fn generate(result: &AllocationResult, format: Format) -> String {
+    generate_with(result, format, &ReportOptions::default())
+}
+
+pub fn generate_with(result: &AllocationResult, format: Format, options: &ReportOptions) -> String {
+    match format {
+        Format::Text => generate_report(result),
+        Format::Json => generate_json(result),
+        Format::Wide => generate_wide(&[(String::new(), result.clone())]),
+        Format::Markdown => markdown::generate(&[(String::new(), result.clone())], options),
+        Format::Html => html::generate(&[(String::new(), result.clone())], options),
+        Format::Latex => latex::generate(&[(String::new(), result.clone())], options),
+        Format::Typst => typst::generate(&[(String::new(), result.clone())], options),
+    }
+}
+
+/// Results of several districts, one after the other (or as a JSON array).
+pub fn generate_districts(results: &[(String, AllocationResult)], format: Format) -> String {
+    generate_districts_with(results, format, &ReportOptions::default())
+}
+
+pub fn generate_districts_with(
+    results: &[(String, AllocationResult)],
+    format: Format,
+    options: &ReportOptions,
+) -> String {
+    match format {
+        Format::Markdown => markdown::generate(results, options),
+        Format::Html => html::generate(results, options),
+        Format::Latex => latex::generate(results, options),
+        Format::Typst => typst::generate(results, options),
+        Format::Text => results
+            .iter()
+            .map(|(district, result)| {
+                format!(
+                    "{district}\n{}\n\n{}",
+                    "=".repeat(district.chars().count()),
+                    generate_report(result)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n"),
+        Format::Wide => generate_wide(results),
+        Format::Json => {
+            let results: Vec<serde_json::Value> = results
+                .iter()
+                .map(|(district, result)| {
+                    serde_json::json!({ "district": district, "result": result })
+                })
+                .collect();
+
+            serde_json::to_string_pretty(&results)
+                .expect("allocation results are always serializable")
+        }
+    }
+}
+
+/// Seats won by every party in every district as CSV, with parties as
+/// columns in order of appearance. Parties that didn't run in a district
+/// are left empty.
+pub fn generate_wide(results: &[(String, AllocationResult)]) -> String {
+    let mut parties: Vec<&str> = Vec::new();
+    for (_, result) in results {
+        for party in &result.parties {
+            if !parties.contains(&party.name.as_str()) {
+                parties.push(&party.name);
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let write = |writer: &mut csv::Writer<Vec<u8>>, record: Vec<String>| {
+        writer
+            .write_record(record)
+            .expect("writing to memory can't fail");
+    };
+
+    write(
+        &mut writer,
+        ["district", "seats"]
+            .into_iter()
+            .chain(parties.iter().copied())
+            .map(String::from)
+            .collect(),
+    );
+
+    for (district, result) in results {
+        let seats = parties.iter().map(|&name| {
+            result
+                .party(name)
+                .map_or(String::new(), |p| p.seats.to_string())
+        });
+
+        write(
+            &mut writer,
+            [district.clone(), result.seat_count.to_string()]
+                .into_iter()
+                .chain(seats)
+                .collect(),
+        );
+    }
+
+    String::from_utf8(writer.into_inner().expect("writing to memory can't fail")).unwrap()
+}
+
+/// A party and its quotients, each with whether it won a seat.
+pub type QuotientRow<'a> = (&'a str, Vec<(f64, bool)>);
+
+/// Quotients of every party above the threshold for its first seats (up to
+/// one more than the most seats won by a party), each telling whether it won
+/// a seat. Largest-remainder methods have none.
+pub fn quotient_table(result: &AllocationResult) -> Option<Vec<QuotientRow<'_>>> {
+    result.method.quotient(1, 0)?;
+
+    let columns = result
+        .parties
+        .iter()
+        .map(|p| p.seats + 1)
+        .max()
+        .unwrap_or(1)
+        .min(result.seat_count);
+
+    Some(
+        result
+            .parties
+            .iter()
+            .filter(|p| !p.below_threshold)
+            .map(|party| {
+                let quotients = (0..columns)
+                    .map(|seats| {
+                        let quotient = result.method.quotient(party.votes, seats).unwrap();
+                        (quotient.value(), seats < party.seats)
+                    })
+                    .collect();
+
+                (party.name.as_str(), quotients)
+            })
+            .collect(),
+    )
+}
+
+pub fn generate_json(result: &AllocationResult) -> String {
+    serde_json::to_string_pretty(result).expect("allocation results are always serializable")
+}
+
+/// Text report of the results, laid out by the default template (see
+/// [`crate::template::DEFAULT`]).
+pub fn generate_report(result: &AllocationResult) -> String {
+    Template::default()
+        .render("", result)
+        .expect("the default template renders any result")
+}
+
+pub fn save_to_file(path: &str, contents: &str) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents.as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use crate::{
+    dhondt::{calculate_with, DHondtError, Options, PartyCandidate},
+    election::AllocationResult,
+    i18n::{locale, t, t_with},
+    numeric::{format_num, format_percent},
+    text_table::format_table,
+};
+
+/// Applies swings (in percentage points of the total votes) to some
+/// parties, taking the votes gained from (or giving the votes lost to) the
+/// rest of the parties in proportion to their votes. The total of votes
+/// stays the same. Returns the name of the first unknown party, if any.
+pub fn apply_swings(
+    votes_by_party: &HashMap<String, u32>,
+    swings: &[(String, f64)],
+) -> Result<HashMap<String, u32>, String> {
+    if let Some((party, _)) = swings
+        .iter()
+        .find(|(party, _)| !votes_by_party.contains_key(party))
+    {
+        return Err(party.clone());
+    }
+
+    let total: u32 = votes_by_party.values().sum();
+    let share = |votes: u32| votes as f64 / total as f64 * 100.0;
+    let swing = |party: &str| swings.iter().find(|s| s.0 == party).map(|s| s.1);
+
+    let swung_before: f64 = votes_by_party
+        .iter()
+        .filter(|(p, _)| swing(p).is_some())
+        .map(|(_, &v)| share(v))
+        .sum();
+    let swung_after: f64 = votes_by_party
+        .iter()
+        .filter_map(|(p, &v)| Some((share(v) + swing(p)?).clamp(0.0, 100.0)))
+        .sum::<f64>()
+        .min(100.0);
+
+    let scale = if swung_before < 100.0 {
+        (100.0 - swung_after) / (100.0 - swung_before)
+    } else {
+        0.0
+    };
+
+    Ok(votes_by_party
+        .iter()
+        .map(|(party, &votes)| {
+            let new_share = match swing(party) {
+                Some(points) => (share(votes) + points).clamp(0.0, 100.0),
+                None => share(votes) * scale,
+            };
+
+            (
+                party.clone(),
+                (new_share / 100.0 * total as f64).round() as u32,
+            )
+        })
+        .collect())
+}
+
+fn party_lists(votes_by_party: &HashMap<String, u32>) -> HashMap<PartyCandidate, u32> {
+    votes_by_party
+        .iter()
+        .map(|(party, &votes)| ((party.clone(), String::new()), votes))
+        .collect()
+}
+
+/// Allocates the seats with the votes as given and with the swung ones
+/// (see [`apply_swings`]).
+pub fn simulate(
+    seat_count: u32,
+    votes_by_party: &HashMap<String, u32>,
+    swung: &HashMap<String, u32>,
+    options: &Options,
+) -> Result<(AllocationResult, AllocationResult), DHondtError> {
+    Ok((
+        calculate_with(seat_count, &party_lists(votes_by_party), options)?,
+        calculate_with(seat_count, &party_lists(swung), options)?,
+    ))
+}
+
+pub fn generate_report(
+    swings: &[(String, f64)],
+    before: &AllocationResult,
+    after: &AllocationResult,
+) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{} {}\n",
+        t("Seats to assign:"),
+        before.seat_count
+    ));
+    for (party, points) in swings {
+        let sign = if *points < 0.0 { "-" } else { "+" };
+        let points = format!("{sign}{}", locale().number(points.abs(), None));
+
+        output.push_str(&format!(
+            "{}\n",
+            t_with(
+                "Swing: {party} {points} points",
+                &[("party", party), ("points", &points)]
+            )
+        ));
+    }
+    output.push('\n');
+
+    let mut lines = vec![vec![
+        t("Party").into(),
+        t("Votes").into(),
+        t("Share").into(),
+        t("Seats").into(),
+        t("Simulated votes").into(),
+        t("Share").into(),
+        t("Seats").into(),
+        t("Change").into(),
+    ]];
+
+    for party in &after.parties {
+        let Some(original) = before.party(&party.name) else {
+            continue;
+        };
+        let change = party.seats as i64 - original.seats as i64;
+
+        lines.push(vec![
+            party.name.clone(),
+            format_num(original.votes),
+            format_percent(original.share, 2),
+            original.seats.to_string(),
+            format_num(party.votes),
+            format_percent(party.share, 2),
+            party.seats.to_string(),
+            match change {
+                0 => String::new(),
+                change => format!("{change:+}"),
+            },
+        ]);
+    }
+
+    let mut left_align = vec![false; lines[0].len()];
+    left_align[0] = true;
+
+    output.push_str(&format_table(&lines, &left_align));
+
+    output
+}
@@ -0,0 +1,341 @@
+use calamine::{open_workbook_auto, Data, Reader};
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+use crate::{
+    csv_parser::{Column, ImportOptions},
+    election::{AllocationResult, PartyResults},
+    error::Error,
+    numeric::parse_num,
+};
+
+/// Parses a cell reference like `B3` into a zero-based (row, column).
+fn parse_cell(cell: &str) -> Option<(u32, u32)> {
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let column = letters
+        .to_ascii_uppercase()
+        .bytes()
+        .try_fold(0u32, |column, b| {
+            column.checked_mul(26)?.checked_add((b - b'A') as u32 + 1)
+        })?;
+    let row: u32 = digits.parse().ok().filter(|&r| r > 0)?;
+
+    Some((row - 1, column - 1))
+}
+
+/// Parses a range like `A3:C40` into its zero-based top-left and
+/// bottom-right cells, whichever pair of opposite corners is given.
+fn parse_range(range: &str) -> Option<((u32, u32), (u32, u32))> {
+    let (start, end) = range.split_once(':')?;
+    let (start, end) = (parse_cell(start)?, parse_cell(end)?);
+
+    Some((
+        (start.0.min(end.0), start.1.min(end.1)),
+        (start.0.max(end.0), start.1.max(end.1)),
+    ))
+}
+
+/// Reads results from a sheet of an XLSX, XLS or ODS file (the first one if
+/// none is given), optionally limited to a range like `A3:C40`. The first
+/// row read holds the headers, and rows are returned along with their
+/// number in the sheet.
+pub fn parse_sheet(
+    path: &str,
+    sheet: Option<&str>,
+    range: Option<&str>,
+    options: &ImportOptions,
+) -> Result<Vec<(u64, PartyResults)>, Error> {
+    let mut workbook = open_workbook_auto(path).map_err(|err| match err {
+        calamine::Error::Io(source) => Error::Io {
+            path: path.into(),
+            source,
+        },
+        err => Error::input(path, err.to_string()),
+    })?;
+
+    let sheet = match sheet {
+        Some(sheet) => sheet.to_string(),
+        None => workbook
+            .sheet_names()
+            .first()
+            .cloned()
+            .ok_or_else(|| Error::input(path, "the file has no sheets"))?,
+    };
+
+    let mut cells = workbook
+        .worksheet_range(&sheet)
+        .map_err(|err| Error::input(path, format!("couldn't read sheet {sheet}: {err}")))?;
+
+    if let Some(range) = range {
+        let (start, end) = parse_range(range)
+            .ok_or_else(|| Error::input(path, format!("invalid range '{range}'")))?;
+
+        // Only the part of the range holding data is read
+        if let (Some(first), Some(last)) = (cells.start(), cells.end()) {
+            let start = (start.0.max(first.0), start.1.max(first.1));
+            let end = (end.0.min(last.0), end.1.min(last.1));
+
+            if start.0 > end.0 || start.1 > end.1 {
+                return Err(Error::input(
+                    path,
+                    format!("the range '{range}' has no data in sheet {sheet}"),
+                ));
+            }
+
+            cells = cells.range(start, end);
+        }
+    }
+
+    let first_row = cells.start().map_or(0, |s| s.0) as u64 + 1;
+    let first_column = cells.start().map_or(0, |s| s.1) as u64 + 1;
+    let mut rows = cells.rows().enumerate().skip(options.skip_rows);
+
+    let headers: Vec<String> = match rows.next() {
+        Some((_, row)) => row
+            .iter()
+            .map(|c| c.to_string().trim().to_string())
+            .collect(),
+        None => return Ok(Vec::new()),
+    };
+
+    let find = |column: &Column| {
+        column.find(&headers).ok_or_else(|| Error::Input {
+            file: path.into(),
+            line: Some(first_row + options.skip_rows as u64),
+            column: None,
+            message: format!(
+                "missing column {column} in sheet {sheet} (found {})",
+                headers.join(", ")
+            ),
+        })
+    };
+
+    let party_column = find(&options.columns.party)?;
+    let name_column = options.columns.name.as_ref().map(find).transpose()?;
+    let votes_column = find(&options.columns.votes)?;
+
+    let mut results = Vec::new();
+
+    for (i, row) in rows {
+        if row.iter().all(|c| c.to_string().trim().is_empty()) {
+            continue;
+        }
+
+        let line = first_row + i as u64;
+        let text = |column: usize| {
+            row.get(column)
+                .map_or(String::new(), |c| c.to_string().trim().to_string())
+        };
+
+        let votes = match row.get(votes_column) {
+            Some(Data::Int(votes)) => u32::try_from(*votes).ok(),
+            Some(Data::Float(votes)) if votes.fract() == 0.0 && *votes >= 0.0 => {
+                u32::try_from(*votes as u64).ok()
+            }
+            Some(Data::String(votes)) => parse_num(votes, options.number_format),
+            _ => None,
+        };
+
+        results.push((
+            line,
+            PartyResults {
+                party: text(party_column),
+                name: name_column.map_or(String::new(), text),
+                votes: votes.ok_or_else(|| Error::Input {
+                    file: path.into(),
+                    line: Some(line),
+                    column: Some(first_column + votes_column as u64),
+                    message: format!("invalid number of votes '{}'", text(votes_column)),
+                })?,
+            },
+        ));
+    }
+
+    Ok(results)
+}
+
+fn write_headers(sheet: &mut Worksheet, headers: &[&str], bold: &Format) -> Result<(), XlsxError> {
+    for (i, header) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, i as u16, *header, bold)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+
+    Ok(())
+}
+
+fn write_workbook(path: &str, result: &AllocationResult) -> Result<(), XlsxError> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let percent = Format::new().set_num_format("0.00%");
+    let number = Format::new().set_num_format("#,##0");
+    let quotient = Format::new().set_num_format("#,##0.00");
+    let won = quotient.clone().set_bold().set_background_color("#C6EFCE");
+
+    let summary = workbook.add_worksheet().set_name("Summary")?;
+    write_headers(
+        summary,
+        &["Party", "Votes", "Share", "Seats", "Last quotient"],
+        &bold,
+    )?;
+    for (i, party) in result.parties.iter().enumerate() {
+        let row = i as u32 + 1;
+
+        summary.write_string(row, 0, &party.name)?;
+        summary.write_number_with_format(row, 1, party.votes, &number)?;
+        summary.write_number_with_format(row, 2, party.share / 100.0, &percent)?;
+        if !party.below_threshold {
+            summary.write_number(row, 3, party.seats)?;
+        }
+        if let Some(last_quotient) = party.last_quotient {
+            summary.write_number_with_format(row, 4, last_quotient, &quotient)?;
+        }
+    }
+    let total_row = result.parties.len() as u32 + 1;
+    summary.write_string_with_format(total_row, 0, "Total", &bold)?;
+    summary.write_number_with_format(total_row, 1, result.total_votes, &number)?;
+    summary.write_number(total_row, 3, result.seat_count)?;
+    summary.autofit();
+
+    let candidates = workbook.add_worksheet().set_name("Candidates")?;
+    write_headers(
+        candidates,
+        // Named as the importer expects, so the sheet can be read back
+        &["Party", "Name", "Votes", "Share", "Elected"],
+        &bold,
+    )?;
+    let mut row = 1;
+    for party in &result.parties {
+        let elected = party.elected.iter().map(|c| (c, true));
+        let not_elected = party.not_elected.iter().map(|c| (c, false));
+
+        for (candidate, is_elected) in elected.chain(not_elected) {
+            candidates.write_string(row, 0, &party.name)?;
+            candidates.write_string(row, 1, &candidate.name)?;
+            candidates.write_number_with_format(row, 2, candidate.votes, &number)?;
+            candidates.write_number_with_format(row, 3, candidate.share / 100.0, &percent)?;
+            candidates.write_boolean(row, 4, is_elected)?;
+            row += 1;
+        }
+    }
+    candidates.autofit();
+
+    // Largest remainders have no quotients to show
+    if result.method.quotient(1, 0).is_some() {
+        let quotients = workbook.add_worksheet().set_name("Quotients")?;
+        quotients.write_string_with_format(0, 0, "Party", &bold)?;
+        for seats in 0..result.seat_count {
+            quotients.write_string_with_format(
+                0,
+                seats as u16 + 1,
+                format!("{}", seats + 1),
+                &bold,
+            )?;
+        }
+        quotients.set_freeze_panes(1, 1)?;
+
+        for (i, party) in result
+            .parties
+            .iter()
+            .filter(|p| !p.below_threshold)
+            .enumerate()
+        {
+            let row = i as u32 + 1;
+            quotients.write_string(row, 0, &party.name)?;
+
+            for seats in 0..result.seat_count {
+                let value = result.method.quotient(party.votes, seats).unwrap().value();
+                let format = if seats < party.seats { &won } else { &quotient };
+
+                quotients.write_number_with_format(row, seats as u16 + 1, value, format)?;
+            }
+        }
+        quotients.autofit();
+    }
+
+    workbook.save(path)
+}
+
+/// Saves the results to an XLSX file, with a summary sheet, a sheet of
+/// candidates and, for highest-averages methods, the table of quotients
+/// with the ones that won seats highlighted.
+pub fn save_xlsx(path: &str, result: &AllocationResult) -> Result<(), Error> {
+    write_workbook(path, result).map_err(|err| Error::Export {
+        path: path.into(),
+        source: match err {
+            XlsxError::IoError(source) => source,
+            err => std::io::Error::other(err),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells() {
+        assert_eq!(parse_cell("A1"), Some((0, 0)));
+        assert_eq!(parse_cell("b3"), Some((2, 1)));
+        assert_eq!(parse_cell("AA10"), Some((9, 26)));
+        assert_eq!(parse_cell("A0"), None);
+        assert_eq!(parse_cell("3"), None);
+        assert_eq!(parse_cell("AAAAAAAAA1"), None);
+        assert_eq!(parse_cell("A99999999999"), None);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("A1:C5"), Some(((0, 0), (4, 2))));
+        assert_eq!(parse_range("C5:A1"), Some(((0, 0), (4, 2))));
+        assert_eq!(parse_range("C1:A5"), Some(((0, 0), (4, 2))));
+        assert_eq!(parse_range("A5:C1"), Some(((0, 0), (4, 2))));
+        assert_eq!(parse_range("A1"), None);
+        assert_eq!(parse_range("A1:"), None);
+    }
+
+    #[test]
+    fn sheet_ranges() {
+        // A table at B2:D5, leaving the first row and column empty
+        let path = std::env::temp_dir().join(format!("dhondt-ranges-{}.xlsx", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        for (i, row) in [
+            ["party", "name", "votes"],
+            ["A", "Ann", "10"],
+            ["A", "Jo", "20"],
+            ["B", "Bo", "30"],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            for (j, cell) in row.into_iter().enumerate() {
+                sheet
+                    .write_string(i as u32 + 1, j as u16 + 1, cell)
+                    .unwrap();
+            }
+        }
+        workbook.save(path).unwrap();
+
+        let read = |range| parse_sheet(path, None, Some(range), &ImportOptions::default());
+
+        for range in ["B2:D5", "D5:B2", "D2:B5", "A1:Z1000", "A1:XFD1048576"] {
+            let rows = read(range).unwrap();
+            let lines: Vec<u64> = rows.iter().map(|(line, _)| *line).collect();
+
+            assert_eq!(lines, [3, 4, 5], "{range}");
+            assert_eq!(rows[2].1.votes, 30, "{range}");
+        }
+        assert_eq!(read("D4:B2").unwrap().len(), 2);
+        assert!(read("F10:G12").is_err());
+        assert!(read("B2:D").is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
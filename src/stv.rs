@@ -0,0 +1,680 @@
+use crate::{
+    dhondt::DHondtError,
+    i18n::{t, t_with},
+    text_table::format_table,
+};
+
+// Tolerance used when comparing fractional vote values
+const EPSILON: f64 = 1e-9;
+// Meek iterations stop once every elected candidate is this close to the quota
+const MEEK_TOLERANCE: f64 = 1e-6;
+const MEEK_MAX_ITERATIONS: u32 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum SurplusMethod {
+    /// Weighted inclusive Gregory method
+    Gregory,
+    /// Meek's iterative method
+    Meek,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ExclusionTieBreak {
+    /// Exclude whoever had fewer votes at the most recent stage where they differ
+    Backwards,
+    /// Exclude whoever had fewer votes at the earliest stage where they differ
+    Forwards,
+    /// Exclude whoever appears last in the ballots
+    Order,
+}
+
+#[derive(Clone, Debug)]
+pub struct Ballot {
+    pub count: u32,
+    pub preferences: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    FirstPreferences,
+    Surplus(usize, f64),
+    Surpluses,
+    Exclusion(usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct Stage {
+    pub action: Action,
+    pub tallies: Vec<f64>,
+    pub exhausted: f64,
+    pub quota: f64,
+    pub elected: Vec<usize>,
+}
+
+pub struct Count {
+    pub candidates: Vec<String>,
+    pub seat_count: u32,
+    pub method: SurplusMethod,
+    pub stages: Vec<Stage>,
+    pub elected: Vec<usize>,
+    pub excluded: Vec<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Hopeful,
+    Elected,
+    Excluded,
+}
+
+struct Parcel {
+    value: f64,
+    preferences: Vec<usize>,
+    next: usize,
+}
+
+/// Hands a parcel to the next hopeful candidate in its preferences, or adds it
+/// to the exhausted votes if there are none left.
+fn place(
+    mut parcel: Parcel,
+    status: &[Status],
+    piles: &mut [Vec<Parcel>],
+    tallies: &mut [f64],
+    exhausted: &mut f64,
+) {
+    while parcel.next < parcel.preferences.len()
+        && status[parcel.preferences[parcel.next]] != Status::Hopeful
+    {
+        parcel.next += 1;
+    }
+
+    match parcel.preferences.get(parcel.next) {
+        Some(&c) => {
+            tallies[c] += parcel.value;
+            piles[c].push(parcel);
+        }
+        None => *exhausted += parcel.value,
+    }
+}
+
+struct Counter<'a> {
+    ballots: &'a [Ballot],
+    seat_count: u32,
+    tie_break: ExclusionTieBreak,
+    status: Vec<Status>,
+    stages: Vec<Stage>,
+    elected: Vec<usize>,
+    excluded: Vec<usize>,
+}
+
+impl Counter<'_> {
+    fn hopeful(&self) -> Vec<usize> {
+        (0..self.status.len())
+            .filter(|&c| self.status[c] == Status::Hopeful)
+            .collect()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elected.len() as u32 >= self.seat_count
+    }
+
+    /// Elects every hopeful candidate that reached the quota in the last
+    /// stage, highest tally first, or everyone left once they can't exceed
+    /// the number of vacancies.
+    fn elect(&mut self) -> Vec<usize> {
+        let stage = self.stages.last().unwrap();
+
+        let mut reached: Vec<usize> = self
+            .hopeful()
+            .into_iter()
+            .filter(|&c| stage.tallies[c] >= stage.quota - EPSILON)
+            .collect();
+        reached.sort_by(|&a, &b| stage.tallies[b].total_cmp(&stage.tallies[a]));
+
+        let vacancies = self.seat_count as usize - self.elected.len();
+        reached.truncate(vacancies);
+
+        for &c in &reached {
+            self.status[c] = Status::Elected;
+            self.elected.push(c);
+        }
+
+        let hopeful = self.hopeful();
+        if !self.is_finished() && self.elected.len() + hopeful.len() <= self.seat_count as usize {
+            let mut remaining = hopeful;
+            remaining.sort_by(|&a, &b| stage.tallies[b].total_cmp(&stage.tallies[a]));
+
+            for &c in &remaining {
+                self.status[c] = Status::Elected;
+                self.elected.push(c);
+            }
+
+            reached.extend(remaining);
+        }
+
+        self.stages.last_mut().unwrap().elected = reached.clone();
+
+        reached
+    }
+
+    fn lowest_hopeful(&self) -> usize {
+        let tallies = &self.stages.last().unwrap().tallies;
+        let hopeful = self.hopeful();
+
+        let lowest = hopeful
+            .iter()
+            .map(|&c| tallies[c])
+            .fold(f64::INFINITY, f64::min);
+        let mut tied: Vec<usize> = hopeful
+            .into_iter()
+            .filter(|&c| tallies[c] <= lowest + EPSILON)
+            .collect();
+
+        let earlier_stages: Vec<&Stage> = match self.tie_break {
+            ExclusionTieBreak::Backwards => self.stages.iter().rev().skip(1).collect(),
+            ExclusionTieBreak::Forwards => self.stages.iter().collect(),
+            ExclusionTieBreak::Order => vec![],
+        };
+
+        for stage in earlier_stages {
+            if tied.len() == 1 {
+                break;
+            }
+
+            let lowest = tied
+                .iter()
+                .map(|&c| stage.tallies[c])
+                .fold(f64::INFINITY, f64::min);
+            tied.retain(|&c| stage.tallies[c] <= lowest + EPSILON);
+        }
+
+        *tied.last().unwrap()
+    }
+
+    fn exclude(&mut self) -> usize {
+        let candidate = self.lowest_hopeful();
+        self.status[candidate] = Status::Excluded;
+        self.excluded.push(candidate);
+
+        candidate
+    }
+
+    fn gregory(&mut self) {
+        let n = self.status.len();
+        let total: f64 = self.ballots.iter().map(|b| b.count as f64).sum();
+        let quota = (total / (self.seat_count as f64 + 1.0)).floor() + 1.0;
+
+        let mut piles: Vec<Vec<Parcel>> = (0..n).map(|_| vec![]).collect();
+        let mut tallies = vec![0.0; n];
+        let mut exhausted = 0.0;
+        let mut pending: Vec<usize> = vec![];
+
+        for ballot in self.ballots {
+            let parcel = Parcel {
+                value: ballot.count as f64,
+                preferences: ballot.preferences.clone(),
+                next: 0,
+            };
+            place(
+                parcel,
+                &self.status,
+                &mut piles,
+                &mut tallies,
+                &mut exhausted,
+            );
+        }
+
+        self.stages.push(Stage {
+            action: Action::FirstPreferences,
+            tallies: tallies.clone(),
+            exhausted,
+            quota,
+            elected: vec![],
+        });
+        pending.extend(self.elect());
+
+        while !self.is_finished() {
+            pending.retain(|&c| tallies[c] > quota + EPSILON);
+            pending.sort_by(|&a, &b| tallies[b].total_cmp(&tallies[a]));
+
+            let action = if !pending.is_empty() {
+                let candidate = pending.remove(0);
+                let surplus = tallies[candidate] - quota;
+                let factor = surplus / tallies[candidate];
+
+                tallies[candidate] = quota;
+                for mut parcel in std::mem::take(&mut piles[candidate]) {
+                    parcel.value *= factor;
+                    place(
+                        parcel,
+                        &self.status,
+                        &mut piles,
+                        &mut tallies,
+                        &mut exhausted,
+                    );
+                }
+
+                Action::Surplus(candidate, surplus)
+            } else {
+                let candidate = self.exclude();
+
+                tallies[candidate] = 0.0;
+                for parcel in std::mem::take(&mut piles[candidate]) {
+                    place(
+                        parcel,
+                        &self.status,
+                        &mut piles,
+                        &mut tallies,
+                        &mut exhausted,
+                    );
+                }
+
+                Action::Exclusion(candidate)
+            };
+
+            self.stages.push(Stage {
+                action,
+                tallies: tallies.clone(),
+                exhausted,
+                quota,
+                elected: vec![],
+            });
+            pending.extend(self.elect());
+        }
+    }
+
+    fn meek(&mut self) {
+        let n = self.status.len();
+        let mut keep = vec![1.0; n];
+
+        let mut action = Action::FirstPreferences;
+
+        loop {
+            let (tallies, exhausted, quota) = self.converge(&mut keep);
+
+            self.stages.push(Stage {
+                action,
+                tallies: tallies.clone(),
+                exhausted,
+                quota,
+                elected: vec![],
+            });
+            let newly_elected = self.elect();
+
+            if self.is_finished() {
+                break;
+            }
+
+            action = if !newly_elected.is_empty() {
+                Action::Surpluses
+            } else {
+                let candidate = self.exclude();
+                keep[candidate] = 0.0;
+
+                Action::Exclusion(candidate)
+            };
+        }
+    }
+
+    /// Recomputes the keep values of elected candidates until their tallies
+    /// settle on the quota, returning the final tallies, exhausted votes and
+    /// quota.
+    fn converge(&self, keep: &mut [f64]) -> (Vec<f64>, f64, f64) {
+        let n = self.status.len();
+        let total: f64 = self.ballots.iter().map(|b| b.count as f64).sum();
+
+        let mut iterations = 0;
+        loop {
+            let mut tallies = vec![0.0; n];
+            let mut exhausted = 0.0;
+
+            for ballot in self.ballots {
+                let mut weight = ballot.count as f64;
+
+                for &c in &ballot.preferences {
+                    let share = weight * keep[c];
+                    tallies[c] += share;
+                    weight -= share;
+
+                    if weight <= EPSILON {
+                        break;
+                    }
+                }
+
+                exhausted += weight.max(0.0);
+            }
+
+            let quota = (total - exhausted) / (self.seat_count as f64 + 1.0);
+
+            let mut converged = true;
+            for &c in &self.elected {
+                if (tallies[c] - quota).abs() > MEEK_TOLERANCE {
+                    converged = false;
+                }
+
+                if tallies[c] > 0.0 {
+                    keep[c] = (keep[c] * quota / tallies[c]).min(1.0);
+                }
+            }
+
+            iterations += 1;
+            if converged || iterations >= MEEK_MAX_ITERATIONS {
+                return (tallies, exhausted, quota);
+            }
+        }
+    }
+}
+
+/// Counts ranked ballots using the Single Transferable Vote with a Droop
+/// quota. Candidates are referred to by their index in `candidates`.
+pub fn count(
+    seat_count: u32,
+    candidates: &[String],
+    ballots: &[Ballot],
+    method: SurplusMethod,
+    tie_break: ExclusionTieBreak,
+) -> Result<Count, DHondtError> {
+    if seat_count == 0 {
+        return Err(DHondtError::ZeroSeats);
+    }
+
+    if candidates.is_empty() {
+        return Err(DHondtError::NoParties);
+    }
+
+    if ballots
+        .iter()
+        .all(|b| b.count == 0 || b.preferences.is_empty())
+    {
+        return Err(DHondtError::NoVotes);
+    }
+
+    let mut counter = Counter {
+        ballots,
+        seat_count: seat_count.min(candidates.len() as u32),
+        tie_break,
+        status: vec![Status::Hopeful; candidates.len()],
+        stages: vec![],
+        elected: vec![],
+        excluded: vec![],
+    };
+
+    match method {
+        SurplusMethod::Gregory => counter.gregory(),
+        SurplusMethod::Meek => counter.meek(),
+    }
+
+    Ok(Count {
+        candidates: candidates.to_vec(),
+        seat_count,
+        method,
+        stages: counter.stages,
+        elected: counter.elected,
+        excluded: counter.excluded,
+    })
+}
+
+pub fn generate_report(count: &Count) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("{} {}\n", t("Seats to assign:"), count.seat_count));
+    output.push_str(&format!(
+        "{} {}\n\n",
+        t("Surplus transfers:"),
+        match count.method {
+            SurplusMethod::Gregory => "Gregory",
+            SurplusMethod::Meek => "Meek",
+        }
+    ));
+
+    let mut header = vec![t("Candidate").into()];
+    header.extend((1..=count.stages.len()).map(|i| t_with("Stage {stage}", &[("stage", &i)])));
+    header.push(String::new());
+
+    let mut lines = vec![header];
+
+    for (c, name) in count.candidates.iter().enumerate() {
+        let mut line = vec![name.clone()];
+        line.extend(count.stages.iter().map(|s| format!("{:.2}", s.tallies[c])));
+        line.push(
+            if count.elected.contains(&c) {
+                "✓"
+            } else {
+                " "
+            }
+            .into(),
+        );
+
+        lines.push(line);
+    }
+
+    lines.push(vec![]);
+
+    let mut exhausted = vec![t("Exhausted").into()];
+    exhausted.extend(count.stages.iter().map(|s| format!("{:.2}", s.exhausted)));
+    lines.push(exhausted);
+
+    let mut quota = vec![t("Quota").into()];
+    quota.extend(count.stages.iter().map(|s| format!("{:.2}", s.quota)));
+    lines.push(quota);
+
+    let mut left_align = vec![false; lines[0].len()];
+    left_align[0] = true;
+
+    output.push_str(&format_table(&lines, &left_align));
+    output.push('\n');
+
+    for (i, stage) in count.stages.iter().enumerate() {
+        let action = match stage.action {
+            Action::FirstPreferences => t("First preferences").into(),
+            Action::Surplus(c, surplus) => t_with(
+                "Surplus of {candidate} ({surplus}) transferred",
+                &[
+                    ("candidate", &count.candidates[c]),
+                    ("surplus", &format!("{surplus:.2}")),
+                ],
+            ),
+            Action::Surpluses => t("Surpluses transferred").into(),
+            Action::Exclusion(c) => t_with(
+                "{candidate} excluded",
+                &[("candidate", &count.candidates[c])],
+            ),
+        };
+
+        output.push_str(&format!(
+            "{}: {action}",
+            t_with("Stage {stage}", &[("stage", &(i + 1))])
+        ));
+
+        if !stage.elected.is_empty() {
+            let elected: Vec<&str> = stage
+                .elected
+                .iter()
+                .map(|&c| count.candidates[c].as_str())
+                .collect();
+            output.push_str(&format!("; {} {}", t("elected:"), elected.join(", ")));
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Candidates and ballots from groups of identical ballots, with the
+    /// candidates numbered in order of appearance as when they're read.
+    fn ballots(groups: &[(u32, &[&str])]) -> (Vec<String>, Vec<Ballot>) {
+        let mut candidates: Vec<String> = Vec::new();
+
+        let ballots = groups
+            .iter()
+            .map(|&(count, names)| {
+                let preferences = names
+                    .iter()
+                    .map(|&name| match candidates.iter().position(|c| c == name) {
+                        Some(index) => index,
+                        None => {
+                            candidates.push(name.into());
+                            candidates.len() - 1
+                        }
+                    })
+                    .collect();
+
+                Ballot { count, preferences }
+            })
+            .collect();
+
+        (candidates, ballots)
+    }
+
+    fn names(count: &Count, indices: &[usize]) -> Vec<String> {
+        indices
+            .iter()
+            .map(|&c| count.candidates[c].clone())
+            .collect()
+    }
+
+    /// The usual example of a 3-seat STV count, with 20 voters choosing
+    /// food for a party.
+    fn food() -> (Vec<String>, Vec<Ballot>) {
+        ballots(&[
+            (4, &["Orange"]),
+            (2, &["Pear", "Orange"]),
+            (8, &["Chocolate", "Strawberry"]),
+            (4, &["Chocolate", "Bonbon"]),
+            (1, &["Strawberry"]),
+            (1, &["Bonbon"]),
+        ])
+    }
+
+    /// A and B tied on six votes for the last exclusion, which is broken
+    /// differently depending on the stage looked at: A had more first
+    /// preferences, but B got the surplus of X before D's exclusion evened
+    /// them out.
+    fn tied() -> (Vec<String>, Vec<Ballot>) {
+        ballots(&[
+            (5, &["A"]),
+            (4, &["B"]),
+            (13, &["X", "B"]),
+            (1, &["D", "A"]),
+            (7, &["E"]),
+        ])
+    }
+
+    #[test]
+    fn gregory() {
+        let (candidates, ballots) = food();
+        let result = count(
+            3,
+            &candidates,
+            &ballots,
+            SurplusMethod::Gregory,
+            ExclusionTieBreak::Backwards,
+        )
+        .unwrap();
+
+        assert_eq!(
+            names(&result, &result.elected),
+            ["Chocolate", "Orange", "Strawberry"]
+        );
+        assert_eq!(names(&result, &result.excluded), ["Pear", "Bonbon"]);
+
+        let chocolate = candidates.iter().position(|c| c == "Chocolate").unwrap();
+        let surplus = &result.stages[1];
+        assert_eq!(surplus.action, Action::Surplus(chocolate, 6.0));
+        // Half of each of Chocolate's ballots moves on
+        assert_eq!(surplus.tallies, [4.0, 2.0, 6.0, 5.0, 3.0]);
+    }
+
+    #[test]
+    fn meek() {
+        let (candidates, ballots) = food();
+        let result = count(
+            3,
+            &candidates,
+            &ballots,
+            SurplusMethod::Meek,
+            ExclusionTieBreak::Backwards,
+        )
+        .unwrap();
+
+        assert_eq!(
+            names(&result, &result.elected),
+            ["Chocolate", "Strawberry", "Orange"]
+        );
+
+        // Chocolate keeps 5/12 of each vote, so Strawberry gets 1 + 8 * 7/12
+        let surpluses = &result.stages[1];
+        assert!((surpluses.tallies[2] - 5.0).abs() < MEEK_TOLERANCE);
+        assert!((surpluses.tallies[3] - (1.0 + 8.0 * 7.0 / 12.0)).abs() < MEEK_TOLERANCE);
+
+        for stage in &result.stages {
+            let counted: f64 = stage.tallies.iter().sum::<f64>() + stage.exhausted;
+            assert!((counted - 20.0).abs() < MEEK_TOLERANCE);
+        }
+
+        // Once elected, a candidate keeps just the quota and passes on the
+        // rest
+        let last = result.stages.last().unwrap();
+        for &c in &result.elected {
+            if !last.elected.contains(&c) {
+                assert!((last.tallies[c] - last.quota).abs() < MEEK_TOLERANCE);
+            }
+        }
+    }
+
+    #[test]
+    fn quotas() {
+        let (candidates, ballots) = food();
+
+        let gregory = count(
+            3,
+            &candidates,
+            &ballots,
+            SurplusMethod::Gregory,
+            ExclusionTieBreak::Backwards,
+        )
+        .unwrap();
+        // Droop quota: floor(20 / 4) + 1
+        assert!(gregory.stages.iter().all(|s| s.quota == 6.0));
+
+        let meek = count(
+            3,
+            &candidates,
+            &ballots,
+            SurplusMethod::Meek,
+            ExclusionTieBreak::Backwards,
+        )
+        .unwrap();
+        assert_eq!(meek.stages[0].quota, 5.0);
+        // Meek's quota shrinks as votes are exhausted
+        for stage in &meek.stages {
+            assert!((stage.quota - (20.0 - stage.exhausted) / 4.0).abs() < MEEK_TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn exclusion_ties() {
+        let (candidates, ballots) = tied();
+        let excluded = |tie_break| {
+            let result =
+                count(2, &candidates, &ballots, SurplusMethod::Gregory, tie_break).unwrap();
+            assert_eq!(names(&result, &result.elected), ["X", "E"]);
+
+            names(&result, &result.excluded)
+        };
+
+        // Stage 2 (after X's surplus): A 5, B 6
+        assert_eq!(excluded(ExclusionTieBreak::Backwards), ["D", "A", "B"]);
+        // Stage 1: A 5, B 4
+        assert_eq!(excluded(ExclusionTieBreak::Forwards), ["D", "B", "A"]);
+        // B appears after A in the ballots
+        assert_eq!(excluded(ExclusionTieBreak::Order), ["D", "B", "A"]);
+    }
+}
@@ -0,0 +1,89 @@
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use crate::{
+    dhondt::{calculate_with, DHondtError, Options, PartyCandidate},
+    election::AllocationResult,
+    i18n::{locale, t, t_with},
+    text_table::format_table,
+};
+
+/// Allocates the same votes for every seat count in a range.
+pub fn sweep(
+    seat_counts: RangeInclusive<u32>,
+    votes: &HashMap<PartyCandidate, u32>,
+    options: &Options,
+) -> Result<Vec<AllocationResult>, DHondtError> {
+    seat_counts
+        .map(|seat_count| calculate_with(seat_count, votes, options))
+        .collect()
+}
+
+/// Table of the seats of every party (as columns) for each seat count,
+/// noting any party that loses a seat when there are more to assign (the
+/// Alabama paradox, which largest-remainder methods can show).
+pub fn generate_report(results: &[AllocationResult]) -> String {
+    let mut output = String::new();
+
+    let Some(last) = results.last() else {
+        return output;
+    };
+
+    output.push_str(&format!("{} {}\n", t("Method:"), last.method.name()));
+    if let Some(threshold) = last.threshold {
+        output.push_str(&format!(
+            "{} {}\n",
+            t("Threshold:"),
+            locale().percent(threshold, None)
+        ));
+    }
+    output.push('\n');
+
+    let parties: Vec<&str> = last.parties.iter().map(|p| p.name.as_str()).collect();
+
+    let mut header = vec![t("Seats").to_string()];
+    header.extend(parties.iter().map(|p| p.to_string()));
+
+    let mut lines = vec![header];
+    for result in results {
+        let mut line = vec![result.seat_count.to_string()];
+        line.extend(parties.iter().map(|&p| {
+            result
+                .party(p)
+                .map_or(String::new(), |p| p.seats.to_string())
+        }));
+
+        lines.push(line);
+    }
+
+    output.push_str(&format_table(&lines, &vec![false; parties.len() + 1]));
+
+    let seats = |result: &AllocationResult, party: &str| result.party(party).map_or(0, |p| p.seats);
+
+    let paradoxes: Vec<String> = results
+        .windows(2)
+        .flat_map(|pair| {
+            parties
+                .iter()
+                .filter(|&&party| seats(&pair[1], party) < seats(&pair[0], party))
+                .map(|party| {
+                    t_with(
+                        "{party} loses a seat going from {from} to {to} seats",
+                        &[
+                            ("party", party),
+                            ("from", &pair[0].seat_count),
+                            ("to", &pair[1].seat_count),
+                        ],
+                    )
+                })
+                .collect::<Vec<String>>()
+        })
+        .collect();
+
+    if !paradoxes.is_empty() {
+        output.push('\n');
+        output.push_str(&paradoxes.join("\n"));
+        output.push('\n');
+    }
+
+    output
+}
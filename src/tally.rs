@@ -0,0 +1,394 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use rayon::prelude::*;
+
+use crate::{dhondt::PartyCandidate, election::PartyResults};
+
+/// A row from a polling-station tally sheet. Individual ballots are read as
+/// rows without a vote count.
+pub struct StationRow {
+    pub line: u64,
+    pub station: String,
+    pub party: String,
+    pub name: String,
+    pub votes: Option<u32>,
+}
+
+pub enum Anomaly {
+    OverVoted {
+        station: String,
+        votes: u32,
+        registered: u32,
+    },
+    DuplicateRow {
+        line: u64,
+        station: String,
+        party: String,
+        name: String,
+    },
+    MissingStation(String),
+    UnknownStation(String),
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Anomaly::OverVoted {
+                station,
+                votes,
+                registered,
+            } => write!(
+                f,
+                "station {station} has {votes} votes but only {registered} registered voters"
+            ),
+            Anomaly::DuplicateRow {
+                line,
+                station,
+                party,
+                name,
+            } => write!(
+                f,
+                "line {line}: station {station} reports {name} ({party}) more than once, row ignored"
+            ),
+            Anomaly::MissingStation(station) => write!(f, "station {station} has no results"),
+            Anomaly::UnknownStation(station) => {
+                write!(f, "station {station} is not in the list of stations")
+            }
+        }
+    }
+}
+
+pub struct Tally {
+    pub results: Vec<PartyResults>,
+    pub votes_by_station: Vec<(String, u32)>,
+    pub anomalies: Vec<Anomaly>,
+}
+
+/// Rows aggregated by each parallel task.
+const CHUNK: usize = 4096;
+
+/// Totals of a chunk of rows, borrowing the names from them. Candidates and
+/// stations are listed in order of appearance.
+#[derive(Default)]
+struct Partial<'a> {
+    candidates: Vec<(&'a str, &'a str, u32)>,
+    candidate_indices: HashMap<(&'a str, &'a str), usize>,
+    stations: Vec<(&'a str, u32)>,
+    station_indices: HashMap<&'a str, usize>,
+    /// Station, candidate and position in the chunk of every row with a vote
+    /// count, to look for repeated rows
+    counted_rows: Vec<(usize, usize, usize)>,
+}
+
+impl<'a> Partial<'a> {
+    fn new(rows: &'a [StationRow]) -> Self {
+        let mut partial = Partial::default();
+
+        for (i, row) in rows.iter().enumerate() {
+            let votes = row.votes.unwrap_or(1);
+
+            let key = (row.party.as_str(), row.name.as_str());
+            let candidate = *partial.candidate_indices.entry(key).or_insert_with(|| {
+                partial.candidates.push((key.0, key.1, 0));
+                partial.candidates.len() - 1
+            });
+            partial.candidates[candidate].2 += votes;
+
+            let station = *partial
+                .station_indices
+                .entry(&row.station)
+                .or_insert_with(|| {
+                    partial.stations.push((&row.station, 0));
+                    partial.stations.len() - 1
+                });
+            partial.stations[station].1 += votes;
+
+            if row.votes.is_some() {
+                partial.counted_rows.push((station, candidate, i));
+            }
+        }
+
+        partial
+    }
+}
+
+/// Adds up tally sheet rows as they are read, in batches. Each batch is
+/// split into chunks that are added up in parallel and then merged in order,
+/// so the results are the same as adding up the rows one by one.
+#[derive(Default)]
+pub struct Aggregator {
+    results: Vec<PartyResults>,
+    result_indices: HashMap<PartyCandidate, usize>,
+    votes_by_station: Vec<(String, u32)>,
+    station_indices: HashMap<String, usize>,
+    /// Stations and candidates (by index) with a row already counted
+    seen_rows: HashSet<(usize, usize)>,
+    anomalies: Vec<Anomaly>,
+}
+
+impl Aggregator {
+    pub fn add(&mut self, rows: &[StationRow]) {
+        let partials: Vec<Partial> = rows.par_chunks(CHUNK).map(Partial::new).collect();
+
+        for (chunk, partial) in rows.chunks(CHUNK).zip(partials) {
+            self.merge(chunk, partial);
+        }
+    }
+
+    fn merge(&mut self, rows: &[StationRow], partial: Partial) {
+        let candidates: Vec<usize> = partial
+            .candidates
+            .iter()
+            .map(|&(party, name, votes)| {
+                let key = (party.to_string(), name.to_string());
+                let i = *self.result_indices.entry(key).or_insert_with(|| {
+                    self.results.push(PartyResults {
+                        party: party.to_string(),
+                        name: name.to_string(),
+                        votes: 0,
+                    });
+                    self.results.len() - 1
+                });
+
+                self.results[i].votes += votes;
+                i
+            })
+            .collect();
+
+        let stations: Vec<usize> = partial
+            .stations
+            .iter()
+            .map(|&(station, votes)| {
+                let i = match self.station_indices.get(station) {
+                    Some(&i) => i,
+                    None => {
+                        self.station_indices
+                            .insert(station.to_string(), self.votes_by_station.len());
+                        self.votes_by_station.push((station.to_string(), 0));
+                        self.votes_by_station.len() - 1
+                    }
+                };
+
+                self.votes_by_station[i].1 += votes;
+                i
+            })
+            .collect();
+
+        // Repeated rows were added up along with the rest, so take them out
+        for &(station, candidate, i) in &partial.counted_rows {
+            if self
+                .seen_rows
+                .insert((stations[station], candidates[candidate]))
+            {
+                continue;
+            }
+
+            let row = &rows[i];
+            let votes = row.votes.unwrap_or_default();
+            self.results[candidates[candidate]].votes -= votes;
+            self.votes_by_station[stations[station]].1 -= votes;
+
+            self.anomalies.push(Anomaly::DuplicateRow {
+                line: row.line,
+                station: row.station.clone(),
+                party: row.party.clone(),
+                name: row.name.clone(),
+            });
+        }
+    }
+
+    /// The results of every candidate, checked against the registered voters
+    /// of every station when a list of stations is given.
+    pub fn finish(self, stations: Option<&[(String, u32)]>) -> Tally {
+        let Aggregator {
+            results,
+            votes_by_station,
+            station_indices,
+            mut anomalies,
+            ..
+        } = self;
+
+        if let Some(stations) = stations {
+            let registered_voters: HashMap<&str, u32> =
+                stations.iter().map(|s| (s.0.as_str(), s.1)).collect();
+
+            for (station, votes) in &votes_by_station {
+                match registered_voters.get(station.as_str()) {
+                    Some(registered) if votes > registered => anomalies.push(Anomaly::OverVoted {
+                        station: station.clone(),
+                        votes: *votes,
+                        registered: *registered,
+                    }),
+                    Some(_) => {}
+                    None => anomalies.push(Anomaly::UnknownStation(station.clone())),
+                }
+            }
+
+            for (station, _) in stations {
+                if !station_indices.contains_key(station) {
+                    anomalies.push(Anomaly::MissingStation(station.clone()));
+                }
+            }
+        }
+
+        Tally {
+            results,
+            votes_by_station,
+            anomalies,
+        }
+    }
+}
+
+/// Aggregates tally sheet rows (or individual ballots) into candidate
+/// results, checking them against the registered voters of every station
+/// when a list of stations is given.
+pub fn tally(rows: &[StationRow], stations: Option<&[(String, u32)]>) -> Tally {
+    let mut aggregator = Aggregator::default();
+    aggregator.add(rows);
+
+    aggregator.finish(stations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows of 40 stations reporting the candidates of 4 parties, with
+    /// individual ballots mixed in and plenty of repeated rows.
+    fn rows(count: usize) -> Vec<StationRow> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move |n: u64| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 33) % n
+        };
+
+        (0..count)
+            .map(|i| {
+                let party = next(4);
+                let name = match next(4) {
+                    0 => String::new(),
+                    n => format!("Candidate {party}.{n}"),
+                };
+
+                StationRow {
+                    line: i as u64 + 2,
+                    station: format!("S{}", next(40)),
+                    party: format!("Party {party}"),
+                    name,
+                    votes: (next(5) != 0).then(|| next(300) as u32),
+                }
+            })
+            .collect()
+    }
+
+    /// Adds up the rows one by one, leaving out repeated rows with a vote
+    /// count.
+    fn sequential(rows: &[StationRow]) -> Tally {
+        let mut results: Vec<PartyResults> = Vec::new();
+        let mut votes_by_station: Vec<(String, u32)> = Vec::new();
+        let mut anomalies = Vec::new();
+        let mut seen = HashSet::new();
+
+        for row in rows {
+            let repeated =
+                row.votes.is_some() && !seen.insert((&row.station, &row.party, &row.name));
+            if repeated {
+                anomalies.push(Anomaly::DuplicateRow {
+                    line: row.line,
+                    station: row.station.clone(),
+                    party: row.party.clone(),
+                    name: row.name.clone(),
+                });
+            }
+
+            // The candidate and the station are listed even if the row is
+            // left out
+            let votes = if repeated { 0 } else { row.votes.unwrap_or(1) };
+
+            match results
+                .iter_mut()
+                .find(|r| r.party == row.party && r.name == row.name)
+            {
+                Some(result) => result.votes += votes,
+                None => results.push(PartyResults {
+                    party: row.party.clone(),
+                    name: row.name.clone(),
+                    votes,
+                }),
+            }
+
+            match votes_by_station.iter_mut().find(|s| s.0 == row.station) {
+                Some(station) => station.1 += votes,
+                None => votes_by_station.push((row.station.clone(), votes)),
+            }
+        }
+
+        Tally {
+            results,
+            votes_by_station,
+            anomalies,
+        }
+    }
+
+    fn totals(tally: &Tally) -> Vec<(&str, &str, u32)> {
+        tally
+            .results
+            .iter()
+            .map(|r| (r.party.as_str(), r.name.as_str(), r.votes))
+            .collect()
+    }
+
+    fn repeated_lines(tally: &Tally) -> Vec<u64> {
+        tally
+            .anomalies
+            .iter()
+            .filter_map(|a| match a {
+                Anomaly::DuplicateRow { line, .. } => Some(*line),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chunks_add_up_as_rows() {
+        let mut rows = rows(3 * CHUNK + 17);
+
+        // Repeat rows right across the chunk boundaries
+        for boundary in [CHUNK, 2 * CHUNK] {
+            let previous = &rows[boundary - 1];
+            rows[boundary] = StationRow {
+                line: rows[boundary].line,
+                station: previous.station.clone(),
+                party: previous.party.clone(),
+                name: previous.name.clone(),
+                votes: Some(7),
+            };
+            rows[boundary - 1].votes = Some(3);
+        }
+
+        let expected = sequential(&rows);
+        let repeated = repeated_lines(&expected);
+        assert!(repeated.contains(&rows[CHUNK].line));
+        assert!(repeated.contains(&rows[2 * CHUNK].line));
+
+        // In batches that don't line up with the chunks either
+        let mut aggregator = Aggregator::default();
+        for batch in [
+            &rows[..CHUNK + 100],
+            &rows[CHUNK + 100..2 * CHUNK + 1],
+            &rows[2 * CHUNK + 1..],
+        ] {
+            aggregator.add(batch);
+        }
+        let tally = aggregator.finish(None);
+
+        assert_eq!(totals(&tally), totals(&expected));
+        assert_eq!(tally.votes_by_station, expected.votes_by_station);
+        assert_eq!(repeated_lines(&tally), repeated);
+    }
+}
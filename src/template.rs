@@ -0,0 +1,171 @@
+use std::path::Path;
+
+use minijinja::{context, value::Value, Environment, ErrorKind};
+
+use crate::{
+    election::AllocationResult,
+    error::Error,
+    i18n::{locale, t},
+    input,
+    numeric::{format_decimal, format_num},
+    text_table::format_table,
+};
+
+/// Template of the text report ([`crate::report::generate_report`]).
+pub const DEFAULT: &str = include_str!("templates/report.txt");
+
+/// A report template (see [`DEFAULT`] for an example), rendered with the
+/// fields of the allocation result along with:
+///
+/// - `method_name` and `district` (empty for data without districts)
+/// - `num`, which groups the thousands of whole numbers
+/// - `decimal(places)`, which also gives a number those decimal places
+/// - `percent(places)`, which writes a percentage
+/// - `t`, which translates one of the messages of the catalogues
+/// - `table("lrr...")`, a block filter laying out lines of tab-separated
+///   cells as columns aligned to the left or right
+/// - `is_tied(party, candidate)`, whether a candidate is tied with an
+///   elected one without being elected themselves
+///
+/// Templates whose name ends in `.html` escape HTML.
+#[derive(Clone, Debug)]
+pub struct Template {
+    name: String,
+    source: String,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Template {
+            name: "default".into(),
+            source: DEFAULT.into(),
+        }
+    }
+}
+
+fn template_error(name: &str, err: minijinja::Error) -> Error {
+    Error::Input {
+        file: name.into(),
+        line: err.line().map(|line| line as u64),
+        column: None,
+        message: match err.detail() {
+            Some(detail) => detail.to_string(),
+            None => err.kind().to_string(),
+        },
+    }
+}
+
+fn num(value: Value) -> Result<String, minijinja::Error> {
+    match f64::try_from(value.clone()) {
+        Ok(number) if number.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&number) => {
+            Ok(format_num(number as u32))
+        }
+        Ok(number) => Ok(locale().number(number, None)),
+        Err(_) => Err(minijinja::Error::new(
+            ErrorKind::InvalidOperation,
+            format!("{value} isn't a number"),
+        )),
+    }
+}
+
+fn decimal(value: f64, places: usize) -> String {
+    format_decimal(value, places)
+}
+
+fn percent(value: f64, places: Option<usize>) -> String {
+    locale().percent(value, places)
+}
+
+fn translate(message: String) -> String {
+    t(&message).to_string()
+}
+
+fn table(text: String, align: String) -> String {
+    let lines: Vec<Vec<String>> = text
+        .lines()
+        .map(|line| line.split('\t').map(str::to_string).collect())
+        .collect();
+    let columns = lines.iter().map(Vec::len).max().unwrap_or_default();
+
+    let mut left_align: Vec<bool> = align.chars().map(|c| c != 'r').collect();
+    left_align.resize(columns.max(left_align.len()), true);
+
+    format_table(&lines, &left_align)
+}
+
+impl Template {
+    /// Reads a template file, checking its syntax.
+    pub fn load(path: &str) -> Result<Template, Error> {
+        let template = Template {
+            name: path.into(),
+            source: input::read_to_string(path)?,
+        };
+
+        template.environment()?;
+
+        Ok(template)
+    }
+
+    /// Extension of the reports the template writes: the one of its file
+    /// name, once any `.j2` or `.jinja` is taken off.
+    pub fn extension(&self) -> &str {
+        let name = self.name.trim_end_matches(".j2").trim_end_matches(".jinja");
+
+        match Path::new(name).extension().and_then(|e| e.to_str()) {
+            Some(extension) => extension,
+            None => "txt",
+        }
+    }
+
+    fn environment(&self) -> Result<Environment<'_>, Error> {
+        let mut env = Environment::new();
+        env.add_filter("num", num);
+        env.add_filter("decimal", decimal);
+        env.add_filter("percent", percent);
+        env.add_filter("t", translate);
+        env.add_filter("table", table);
+        env.add_template(&self.name, &self.source)
+            .map_err(|err| template_error(&self.name, err))?;
+
+        Ok(env)
+    }
+
+    /// Renders the report of a district.
+    pub fn render(&self, district: &str, result: &AllocationResult) -> Result<String, Error> {
+        let mut env = self.environment()?;
+
+        let tied = result.clone();
+        env.add_function("is_tied", move |party: &str, candidate: &str| {
+            tied.is_tied(party, candidate)
+        });
+
+        env.get_template(&self.name)
+            .and_then(|template| {
+                template.render(context! {
+                    method_name => result.method.name(),
+                    district => district,
+                    ..Value::from_serialize(result)
+                })
+            })
+            .map_err(|err| template_error(&self.name, err))
+    }
+
+    /// Renders the reports of several districts, one after the other under
+    /// the name of each as text reports are.
+    pub fn render_districts(
+        &self,
+        results: &[(String, AllocationResult)],
+    ) -> Result<String, Error> {
+        Ok(results
+            .iter()
+            .map(|(district, result)| {
+                Ok(format!(
+                    "{district}\n{}\n\n{}",
+                    "=".repeat(district.chars().count()),
+                    self.render(district, result)?
+                ))
+            })
+            .collect::<Result<Vec<String>, Error>>()?
+            .join("\n"))
+    }
+}
@@ -0,0 +1,32 @@
+/// Lays out rows as space-padded columns. `left_align` tells, for every
+/// column, whether it should be aligned to the left (otherwise it's aligned
+/// to the right).
+pub fn format_table(lines: &[Vec<String>], left_align: &[bool]) -> String {
+    let mut output = String::new();
+    let mut max_widths = vec![0; left_align.len()];
+
+    for line in lines {
+        for (width, col) in max_widths.iter_mut().zip(line) {
+            *width = (*width).max(col.chars().count());
+        }
+    }
+
+    for line in lines {
+        let cols: Vec<String> = line
+            .iter()
+            .zip(max_widths.iter().zip(left_align))
+            .map(|(col, (&width, &left))| {
+                if left {
+                    format!("{col:<width$}")
+                } else {
+                    format!("{col:>width$}")
+                }
+            })
+            .collect();
+
+        output.push_str(cols.join("    ").trim_end());
+        output.push('\n');
+    }
+
+    output
+}
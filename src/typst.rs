@@ -0,0 +1,212 @@
+use crate::{
+    election::{AllocationResult, Tie},
+    i18n::{locale, t, t_with},
+    numeric::format_grouped,
+    report::{quotient_table, ReportOptions},
+};
+
+/// Escapes text for Typst markup, where most symbols start some syntax.
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' | '*' | '_' | '`' | '$' | '#' | '[' | ']' | '<' | '>' | '@' | '~' | '/' | '-'
+            | '=' | '+' | '"' | '\'' => format!("\\{c}"),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+fn cells(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|cell| format!("[{cell}]"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Writes a three-line table (like LaTeX's `booktabs`) in a figure, with
+/// the first row of `lines` as its header.
+fn table(output: &mut String, caption: &str, align: &[&str], lines: &[Vec<String>]) {
+    output.push_str("#figure(\n  table(\n");
+    output.push_str(&format!(
+        "    columns: {},\n    align: ({}),\n    stroke: none,\n    table.hline(),\n",
+        align.len(),
+        align.join(", ")
+    ));
+
+    if let Some((header, rows)) = lines.split_first() {
+        let header: Vec<String> = header.iter().map(|cell| format!("*{cell}*")).collect();
+
+        output.push_str(&format!("    table.header({}),\n", cells(&header)));
+        output.push_str("    table.hline(stroke: 0.5pt),\n");
+        for line in rows {
+            output.push_str(&format!("    {},\n", cells(line)));
+        }
+    }
+
+    output.push_str("    table.hline(),\n  ),\n");
+    output.push_str(&format!("  caption: [{caption}],\n)\n"));
+}
+
+fn bold(text: String) -> String {
+    format!("*{text}*")
+}
+
+fn generate_result(
+    output: &mut String,
+    district: &str,
+    result: &AllocationResult,
+    options: &ReportOptions,
+) {
+    let style = options.numbers;
+    // Thin space
+    let separator = style.grouping.then_some("\u{2009}");
+    let decimal = locale().separators().1;
+    let number = |value: f64, decimals: usize| format_grouped(value, decimals, decimal, separator);
+    let caption = |title: &str| match district {
+        "" => title.to_string(),
+        district => format!("{title}: {}", escape(district)),
+    };
+
+    output.push_str(&format!(
+        "// {} seats, {}",
+        result.seat_count,
+        result.method.name()
+    ));
+    if let Some(threshold) = result.threshold {
+        output.push_str(&format!(", threshold {threshold}%"));
+    }
+    output.push('\n');
+
+    let mut lines = vec![vec![
+        t("Party").to_string(),
+        t("Votes").into(),
+        format!("{} (%)", t("Share")),
+        t("Seats").into(),
+    ]];
+    for party in &result.parties {
+        lines.push(vec![
+            escape(&party.name),
+            number(party.votes as f64, 0),
+            number(party.share, style.decimals),
+            if party.below_threshold {
+                "–".into()
+            } else {
+                party.seats.to_string()
+            },
+        ]);
+    }
+    lines.push(vec![
+        t("Total").into(),
+        number(result.total_votes as f64, 0),
+        String::new(),
+        result.seat_count.to_string(),
+    ]);
+
+    table(
+        output,
+        &caption(t("Seat distribution")),
+        &["left", "right", "right", "right"],
+        &lines,
+    );
+
+    if result.has_candidates() {
+        let mut lines = vec![vec![
+            t("Party").to_string(),
+            t("Candidate").into(),
+            t("Votes").into(),
+            format!("{} (%)", t("Share")),
+        ]];
+
+        for party in &result.parties {
+            let candidates = party
+                .elected
+                .iter()
+                .map(|c| (c, true))
+                .chain(party.not_elected.iter().map(|c| (c, false)));
+
+            for (candidate, elected) in candidates {
+                let name = escape(&candidate.name);
+
+                lines.push(vec![
+                    escape(&party.name),
+                    if elected { bold(name) } else { name },
+                    number(candidate.votes as f64, 0),
+                    number(candidate.share, style.decimals),
+                ]);
+            }
+        }
+
+        output.push('\n');
+        table(
+            output,
+            &caption(t("Candidates (elected in bold)")),
+            &["left", "left", "right", "right"],
+            &lines,
+        );
+    }
+
+    if let Some(quotients) = quotient_table(result).filter(|_| options.quotients) {
+        let columns = quotients.first().map_or(0, |(_, q)| q.len());
+
+        let mut lines = vec![std::iter::once(t("Party").to_string())
+            .chain((1..=columns).map(|seat| seat.to_string()))
+            .collect::<Vec<_>>()];
+        for (party, quotients) in quotients {
+            lines.push(
+                std::iter::once(escape(party))
+                    .chain(quotients.into_iter().map(|(value, won)| {
+                        let value = number(value, style.decimals);
+                        if won {
+                            bold(value)
+                        } else {
+                            value
+                        }
+                    }))
+                    .collect(),
+            );
+        }
+
+        let align: Vec<&str> = std::iter::once("left")
+            .chain(std::iter::repeat_n("right", columns))
+            .collect();
+
+        output.push('\n');
+        table(
+            output,
+            &caption(t("Quotients (seats won in bold)")),
+            &align,
+            &lines,
+        );
+    }
+
+    for tie in &result.ties {
+        if let Tie::Seat { seat, parties } = tie {
+            output.push_str(&format!(
+                "// {}\n",
+                t_with(
+                    "Seat {seat} was tied between {parties}",
+                    &[("seat", seat), ("parties", &parties.join(", "))]
+                )
+            ));
+        }
+    }
+    for warning in &result.warnings {
+        output.push_str(&format!("// {} {warning}\n", t("Warning:")));
+    }
+}
+
+/// Typst tables of the results of every district, to include in a
+/// document.
+pub fn generate(results: &[(String, AllocationResult)], options: &ReportOptions) -> String {
+    let mut output = String::new();
+
+    for (i, (district, result)) in results.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        generate_result(&mut output, district, result, options);
+    }
+
+    output
+}
@@ -7,10 +7,12 @@ use cursive::{
 use cursive_table_view::TableView;
 use std::sync::Mutex;
 
-use crate::ui::{
-    read_input,
-    table_columns::{PartyResults, PartyResultsColumn},
-    validation::validate_number,
+use crate::{
+    election::PartyResults,
+    i18n::t,
+    ui::{
+        field_labels, read_input, table_columns::PartyResultsColumn, validation::validate_number,
+    },
 };
 
 use super::error_msgbox;
@@ -22,12 +24,12 @@ pub fn add_party(s: &mut Cursive) {
     let name = read_input(s, "name").unwrap();
 
     if party.trim().is_empty() {
-        error_msgbox(s, "Please provide a party name");
+        error_msgbox(s, t("Please provide a party name"));
         return;
     }
 
     if name.trim().is_empty() {
-        error_msgbox(s, "Please provide a name");
+        error_msgbox(s, t("Please provide a name"));
         return;
     }
 
@@ -44,7 +46,7 @@ pub fn add_party(s: &mut Cursive) {
     .unwrap();
 
     if is_name_repeated {
-        error_msgbox(s, "Candidate has already been entered");
+        error_msgbox(s, t("Candidate has already been entered"));
         return;
     }
 
@@ -70,32 +72,34 @@ pub fn add_party(s: &mut Cursive) {
 }
 
 pub fn new_party_dialog(s: &mut Cursive) {
+    let labels = field_labels(["Party:", "Name:", "Votes:"]);
+
     s.add_layer(
         OnEventView::new(
             Dialog::around(
                 LinearLayout::vertical()
                     .child(
                         LinearLayout::horizontal()
-                            .child(TextView::new("Party: "))
+                            .child(TextView::new(labels[0].clone()))
                             .child(EditView::new().with_name("party").fixed_width(25)),
                     )
                     .child(
                         LinearLayout::horizontal()
-                            .child(TextView::new("Name:  "))
+                            .child(TextView::new(labels[1].clone()))
                             .child(EditView::new().with_name("name").fixed_width(25)),
                     )
                     .child(
                         OnEventView::new(
                             LinearLayout::horizontal()
-                                .child(TextView::new("Votes: "))
+                                .child(TextView::new(labels[2].clone()))
                                 .child(EditView::new().with_name("votes").fixed_width(9)),
                         )
                         .on_event(Key::Enter, add_party),
                     ),
             )
-            .title("Add candidate")
-            .button("OK", add_party)
-            .dismiss_button("Cancel"),
+            .title(t("Add candidate"))
+            .button(t("OK"), add_party)
+            .dismiss_button(t("Cancel")),
         )
         .on_event(Key::Esc, |s| {
             s.pop_layer();
@@ -0,0 +1,115 @@
+use cursive::{
+    view::{Nameable, Resizable},
+    views::{Dialog, LinearLayout, SelectView, TextView},
+    Cursive,
+};
+use cursive_table_view::TableView;
+
+use crate::{
+    csv_parser::{parse_file_with, read_headers, Column, ColumnMapping, ImportOptions},
+    election::PartyResults,
+    i18n::{t, t_with},
+    ui::{sentence_case, table_columns::PartyResultsColumn},
+    validate::{describe, find_duplicate, merge_duplicates},
+};
+
+use super::error_msgbox;
+
+fn column_select(headers: &[String], optional: bool) -> SelectView<Option<usize>> {
+    let mut select = SelectView::new().popup();
+
+    if optional {
+        select.add_item(t("(none)"), None);
+    }
+    for (i, header) in headers.iter().enumerate() {
+        select.add_item(format!("{}: {header}", i + 1), Some(i + 1));
+    }
+
+    select
+}
+
+fn selected(s: &mut Cursive, name: &str) -> Option<usize> {
+    s.call_on_name(name, |view: &mut SelectView<Option<usize>>| {
+        view.selection().and_then(|c| *c)
+    })
+    .unwrap()
+}
+
+/// Asks which columns of a file hold the party, candidate and votes, and
+/// loads it into the main table.
+pub fn column_mapping_dialog(s: &mut Cursive, path: String, import: ImportOptions) {
+    let headers = match read_headers(&path, &import) {
+        Ok(headers) => headers,
+        Err(err) => {
+            error_msgbox(s, &sentence_case(&err.to_string()));
+            return;
+        }
+    };
+
+    let row = |label: &str, select: SelectView<Option<usize>>, name: &str| {
+        LinearLayout::horizontal()
+            .child(TextView::new(label).fixed_width(12))
+            .child(select.with_name(name).min_width(30))
+    };
+
+    s.add_layer(
+        Dialog::around(
+            LinearLayout::vertical()
+                .child(TextView::new(format!(
+                    "{}\n",
+                    t_with(
+                        "The columns of {path} don't match party, name and votes.\nPick the ones to use:",
+                        &[("path", &path)]
+                    )
+                )))
+                .child(row(t("Party:"), column_select(&headers, false), "party_column"))
+                .child(row(t("Candidate:"), column_select(&headers, true), "name_column"))
+                .child(row(t("Votes:"), column_select(&headers, false), "votes_column")),
+        )
+        .title(t("Import columns"))
+        .dismiss_button(t("Cancel"))
+        .button(t("Import"), move |s| {
+            let (Some(party), Some(votes)) = (selected(s, "party_column"), selected(s, "votes_column"))
+            else {
+                return;
+            };
+
+            let import = ImportOptions {
+                columns: ColumnMapping {
+                    party: Column::Position(party),
+                    name: selected(s, "name_column").map(Column::Position),
+                    votes: Column::Position(votes),
+                },
+                ..import.clone()
+            };
+
+            match parse_file_with(&path, &import) {
+                Ok(results) => {
+                    let results = if import.merge_duplicates {
+                        merge_duplicates(&results)
+                    } else if let Some(row) = find_duplicate(&results) {
+                        error_msgbox(
+                            s,
+                            &sentence_case(&t_with(
+                                "{candidate} is given more than once",
+                                &[("candidate", &describe(&row.party, &row.name))],
+                            )),
+                        );
+                        return;
+                    } else {
+                        results
+                    };
+
+                    s.pop_layer();
+                    s.call_on_name(
+                        "votes_table",
+                        |table: &mut TableView<PartyResults, PartyResultsColumn>| {
+                            table.set_items(results);
+                        },
+                    );
+                }
+                Err(err) => error_msgbox(s, &sentence_case(&err.to_string())),
+            }
+        }),
+    );
+}
@@ -4,7 +4,7 @@ use cursive::{
 };
 use cursive_table_view::TableView;
 
-use crate::ui::table_columns::{PartyResults, PartyResultsColumn};
+use crate::{election::PartyResults, i18n::t, ui::table_columns::PartyResultsColumn};
 
 pub fn confirm_quit(s: &mut Cursive) {
     let is_votes_table_empty = s
@@ -27,10 +27,10 @@ pub fn confirm_quit(s: &mut Cursive) {
     }
 
     s.add_layer(
-        Dialog::text("Do you really want to quit?")
-            .title("Confirm quit")
-            .dismiss_button("No")
-            .button("Yes", |s| {
+        Dialog::text(t("Do you really want to quit?"))
+            .title(t("Confirm quit"))
+            .dismiss_button(t("No"))
+            .button(t("Yes"), |s| {
                 s.quit();
             }),
     );
@@ -57,10 +57,10 @@ pub fn confirm_clear(s: &mut Cursive) {
     }
 
     s.add_layer(
-        Dialog::text("Do you really want to clear all data?")
-            .title("Confirm clear")
-            .dismiss_button("No")
-            .button("Yes", |s| {
+        Dialog::text(t("Do you really want to clear all data?"))
+            .title(t("Confirm clear"))
+            .dismiss_button(t("No"))
+            .button(t("Yes"), |s| {
                 s.call_on_name(
                     "votes_table",
                     move |table: &mut TableView<PartyResults, PartyResultsColumn>| {
@@ -79,10 +79,10 @@ pub fn confirm_clear(s: &mut Cursive) {
 
 pub fn confirm_delete(s: &mut Cursive, index: usize) {
     s.add_layer(
-        Dialog::text("Do you want to remove this candidate?")
-            .title("Confirm")
-            .dismiss_button("No")
-            .button("Yes", move |s| {
+        Dialog::text(t("Do you want to remove this candidate?"))
+            .title(t("Confirm"))
+            .dismiss_button(t("No"))
+            .button(t("Yes"), move |s| {
                 s.call_on_name(
                     "votes_table",
                     |table: &mut TableView<PartyResults, PartyResultsColumn>| {
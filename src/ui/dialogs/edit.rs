@@ -8,10 +8,12 @@ use cursive::{
 };
 use cursive_table_view::TableView;
 
-use crate::ui::{
-    read_input,
-    table_columns::{PartyResults, PartyResultsColumn},
-    validation::validate_number,
+use crate::{
+    election::PartyResults,
+    i18n::t,
+    ui::{
+        field_labels, read_input, table_columns::PartyResultsColumn, validation::validate_number,
+    },
 };
 
 use super::{confirm::confirm_delete, error_msgbox};
@@ -21,12 +23,12 @@ pub fn edit_party(s: &mut Cursive, index: usize) {
     let name = read_input(s, "edit_name").unwrap();
 
     if party.trim().is_empty() {
-        error_msgbox(s, "Please provide a party name");
+        error_msgbox(s, t("Please provide a party name"));
         return;
     }
 
     if name.trim().is_empty() {
-        error_msgbox(s, "Please provide a name");
+        error_msgbox(s, t("Please provide a name"));
         return;
     }
 
@@ -68,7 +70,7 @@ pub fn edit_party(s: &mut Cursive, index: usize) {
     }
 
     if is_name_repeated {
-        error_msgbox(s, "Candidate has already been entered");
+        error_msgbox(s, t("Candidate has already been entered"));
         return;
     }
 
@@ -91,24 +93,26 @@ pub fn edit_party(s: &mut Cursive, index: usize) {
 }
 
 pub fn edit_party_dialog(s: &mut Cursive, _row: usize, index: usize) {
+    let labels = field_labels(["Party:", "Name:", "Votes:"]);
+
     s.add_layer(
         OnEventView::new(
             Dialog::around(
                 LinearLayout::vertical()
                     .child(
                         LinearLayout::horizontal()
-                            .child(TextView::new("Party: "))
+                            .child(TextView::new(labels[0].clone()))
                             .child(EditView::new().with_name("edit_party").fixed_width(26)),
                     )
                     .child(
                         LinearLayout::horizontal()
-                            .child(TextView::new("Name:  "))
+                            .child(TextView::new(labels[1].clone()))
                             .child(EditView::new().with_name("edit_name").fixed_width(26)),
                     )
                     .child(
                         OnEventView::new(
                             LinearLayout::horizontal()
-                                .child(TextView::new("Votes: "))
+                                .child(TextView::new(labels[2].clone()))
                                 .child(EditView::new().with_name("edit_votes").fixed_width(9)),
                         )
                         .on_event(Key::Enter, move |s: &mut Cursive| {
@@ -116,12 +120,12 @@ pub fn edit_party_dialog(s: &mut Cursive, _row: usize, index: usize) {
                         }),
                     ),
             )
-            .title("Edit party")
-            .button("OK", move |s: &mut Cursive| {
+            .title(t("Edit party"))
+            .button(t("OK"), move |s: &mut Cursive| {
                 edit_party(s, index);
             })
-            .dismiss_button("Cancel")
-            .button("Delete", move |s: &mut Cursive| {
+            .dismiss_button(t("Cancel"))
+            .button(t("Delete"), move |s: &mut Cursive| {
                 confirm_delete(s, index);
             }),
         )
@@ -0,0 +1,92 @@
+use std::{thread, time::Duration};
+
+use cursive::{
+    event::Key,
+    view::{Nameable, Resizable},
+    views::{Dialog, OnEventView, ScrollView, TextView},
+    Cursive,
+};
+
+use crate::{
+    dhondt::{DHondtError, Options},
+    i18n::t,
+    live::{generate_progress_report, read_tally, tally_signature},
+    ui::sentence_case,
+};
+
+use super::save::save_dialog;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shows the results of a partial count, recomputing them in the background
+/// every time the tally files change.
+pub fn show(
+    s: &mut Cursive,
+    seat_count: u32,
+    tally_path: String,
+    stations: Vec<(String, u32)>,
+    options: Options,
+) {
+    s.add_layer(
+        OnEventView::new(
+            Dialog::around(ScrollView::new(
+                TextView::new(t("Waiting for results...")).with_name("live_report"),
+            ))
+            .title(t("Live count"))
+            .button(t("Save"), |s| {
+                let report = s
+                    .call_on_name("live_report", |view: &mut TextView| {
+                        view.get_content().source().to_string()
+                    })
+                    .unwrap();
+
+                save_dialog(s, report);
+            })
+            .button(t("Quit"), |s| s.quit())
+            .min_width(70),
+        )
+        .on_event(Key::Esc, |s| s.quit()),
+    );
+
+    let cb_sink = s.cb_sink().clone();
+
+    thread::spawn(move || {
+        let mut last_signature = None;
+
+        loop {
+            let signature = tally_signature(&tally_path);
+
+            if last_signature.as_ref() != Some(&signature) {
+                // A row still being written shows as an error until the file
+                // changes again
+                let content = match read_tally(&tally_path) {
+                    Ok(rows) => {
+                        match generate_progress_report(seat_count, &rows, &stations, &options) {
+                            Ok(report) => report,
+                            // Nothing has been counted yet
+                            Err(DHondtError::NoParties | DHondtError::NoVotes) => {
+                                t("Waiting for results...").into()
+                            }
+                            Err(err) => sentence_case(t(&err.to_string())),
+                        }
+                    }
+                    Err(err) => sentence_case(&err.to_string()),
+                };
+
+                let sent = cb_sink.send(Box::new(move |s| {
+                    s.call_on_name("live_report", |view: &mut TextView| {
+                        view.set_content(content);
+                    });
+                }));
+
+                if sent.is_err() {
+                    break;
+                }
+
+                last_signature = Some(signature);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
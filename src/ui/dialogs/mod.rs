@@ -1,15 +1,23 @@
 pub mod add;
+pub mod columns;
 pub mod confirm;
 pub mod edit;
+pub mod live;
 pub mod results;
 pub mod save;
 
 use cursive::{views::Dialog, Cursive};
 
+use crate::i18n::t;
+
 pub fn error_msgbox(s: &mut Cursive, message: &str) {
-    s.add_layer(Dialog::text(message).title("Error").dismiss_button("OK"));
+    s.add_layer(
+        Dialog::text(message)
+            .title(t("Error"))
+            .dismiss_button(t("OK")),
+    );
 }
 
 pub fn msgbox(s: &mut Cursive, title: &str, message: &str) {
-    s.add_layer(Dialog::text(message).title(title).dismiss_button("OK"));
+    s.add_layer(Dialog::text(message).title(title).dismiss_button(t("OK")));
 }
@@ -1,45 +1,74 @@
-use std::{fs::File, io::Write, path::Path};
+use std::path::Path;
 
 use cursive::{
     event::Key,
     view::{Nameable, Resizable},
-    views::{Dialog, EditView, LinearLayout, OnEventView, TextView},
+    views::{Checkbox, Dialog, EditView, LinearLayout, OnEventView, SelectView, TextView},
     Cursive,
 };
 
-use crate::ui::read_input;
+use cursive_table_view::TableView;
 
-use super::{error_msgbox, msgbox};
+use crate::{
+    chart::{hemicycle, share_bars},
+    csv_parser::parse_metadata,
+    dhondt::Options,
+    election::{AllocationResult, PartyResults},
+    i18n::t,
+    metadata::Metadata,
+    project::generate_project,
+    report::{generate_with, save_to_file, Format, ReportOptions},
+    template::Template,
+    ui::{read_input, sentence_case, table_columns::PartyResultsColumn},
+};
 
-pub fn save_to_file(path: &str, contents: &str) -> std::io::Result<()> {
-    let mut file = File::create(path)?;
-    file.write_all(contents.as_bytes())?;
+use super::{error_msgbox, msgbox};
 
-    Ok(())
+pub fn save_dialog(s: &mut Cursive, results: String) {
+    save_dialog_with(s, LinearLayout::vertical(), move |_, _| Ok(results.clone()));
 }
 
-pub fn save_dialog(s: &mut Cursive, results: String) {
+/// Asks for a path to save to, with extra options below it, and saves what
+/// `contents` makes for that path (with the options set), or shows the
+/// error it gives.
+fn save_dialog_with(
+    s: &mut Cursive,
+    options: LinearLayout,
+    contents: impl Fn(&mut Cursive, &str) -> Result<String, String> + Send + Sync + 'static,
+) {
     s.add_layer(
         OnEventView::new(
             Dialog::around(
-                LinearLayout::horizontal()
-                    .child(TextView::new("Path: "))
-                    .child(EditView::new().with_name("save_path").fixed_width(25)),
+                LinearLayout::vertical()
+                    .child(
+                        LinearLayout::horizontal()
+                            .child(TextView::new(format!("{} ", t("Path:"))))
+                            .child(EditView::new().with_name("save_path").fixed_width(25)),
+                    )
+                    .child(options),
             )
-            .title("Save to file")
-            .dismiss_button("Cancel")
-            .button("Save", move |s| {
+            .title(t("Save to file"))
+            .dismiss_button(t("Cancel"))
+            .button(t("Save"), move |s| {
                 let path = read_input(s, "save_path").unwrap();
 
                 if path.trim().is_empty() {
-                    error_msgbox(s, "Path must not be empty");
+                    error_msgbox(s, t("Path must not be empty"));
                     return;
                 }
 
+                let results = match contents(s, &path) {
+                    Ok(results) => results,
+                    Err(err) => {
+                        error_msgbox(s, &err);
+                        return;
+                    }
+                };
+
                 if Path::exists(Path::new(path.as_str())) {
                     s.add_layer(
-                        Dialog::text("File already exists. Do you want to overwrite?")
-                            .button("Yes", {
+                        Dialog::text(t("File already exists. Do you want to overwrite?"))
+                            .button(t("Yes"), {
                                 let results = results.clone();
 
                                 move |s| {
@@ -47,16 +76,20 @@ pub fn save_dialog(s: &mut Cursive, results: String) {
                                     match save_to_file(path.as_str(), &results.clone()) {
                                         Ok(_) => {
                                             s.pop_layer();
-                                            msgbox(s, "Save to file", "File saved successfully");
+                                            msgbox(
+                                                s,
+                                                t("Save to file"),
+                                                t("File saved successfully"),
+                                            );
                                         }
                                         Err(err) => error_msgbox(
                                             s,
-                                            format!("Could not save results: {}", err).as_str(),
+                                            &format!("{} {err}", t("Could not save results:")),
                                         ),
                                     }
                                 }
                             })
-                            .button("No", |s| {
+                            .button(t("No"), |s| {
                                 s.pop_layer();
                             }),
                     );
@@ -66,9 +99,9 @@ pub fn save_dialog(s: &mut Cursive, results: String) {
                 match save_to_file(path.as_str(), &results.clone()) {
                     Ok(_) => {
                         s.pop_layer();
-                        msgbox(s, "Save to file", "File saved successfully");
+                        msgbox(s, t("Save to file"), t("File saved successfully"));
                     }
-                    Err(_) => error_msgbox(s, "Could not save results"),
+                    Err(_) => error_msgbox(s, t("Could not save results")),
                 }
             }),
         )
@@ -77,3 +110,136 @@ pub fn save_dialog(s: &mut Cursive, results: String) {
         }),
     );
 }
+
+/// Saves the results with the template file given, or else in the format
+/// picked (or the one matching the extension of the path).
+pub fn save_results_dialog(s: &mut Cursive, result: AllocationResult) {
+    let formats = SelectView::<Option<Format>>::new()
+        .popup()
+        .item(t("From extension"), None)
+        .item(t("Text"), Some(Format::Text))
+        .item("Markdown", Some(Format::Markdown))
+        .item("HTML", Some(Format::Html))
+        .item("LaTeX", Some(Format::Latex))
+        .item("Typst", Some(Format::Typst))
+        .item("JSON", Some(Format::Json))
+        .with_name("save_format");
+
+    let options = LinearLayout::vertical()
+        .child(
+            LinearLayout::horizontal()
+                .child(TextView::new(format!("{} ", t("Format:"))))
+                .child(formats),
+        )
+        .child(
+            LinearLayout::horizontal()
+                .child(Checkbox::new().with_name("save_quotients"))
+                .child(TextView::new(format!(" {}", t("Include quotient table")))),
+        )
+        .child(parties_input())
+        .child(
+            LinearLayout::horizontal()
+                .child(TextView::new(format!("{} ", t("Template file:"))))
+                .child(EditView::new().with_name("save_template").fixed_width(25)),
+        );
+
+    save_dialog_with(s, options, move |s, path| {
+        if let Some(template) = read_input(s, "save_template").filter(|t| !t.trim().is_empty()) {
+            return Template::load(template.trim())
+                .and_then(|template| template.render("", &result))
+                .map_err(|err| sentence_case(&err.to_string()));
+        }
+
+        let format = s
+            .call_on_name("save_format", |view: &mut SelectView<Option<Format>>| {
+                view.selection().and_then(|format| *format)
+            })
+            .flatten()
+            .or_else(|| Format::from_path(path))
+            .unwrap_or_default();
+        let quotients = s
+            .call_on_name("save_quotients", |view: &mut Checkbox| view.is_checked())
+            .unwrap_or_default();
+
+        Ok(generate_with(
+            &result,
+            format,
+            &ReportOptions {
+                quotients,
+                metadata: read_metadata(s)?,
+                ..Default::default()
+            },
+        ))
+    });
+}
+
+/// Field for the path of a party metadata file (see [`read_metadata`]).
+fn parties_input() -> LinearLayout {
+    LinearLayout::horizontal()
+        .child(TextView::new(format!("{} ", t("Party colours file:"))))
+        .child(EditView::new().with_name("save_parties").fixed_width(25))
+}
+
+/// Reads the party metadata file given in the dialog, if any.
+fn read_metadata(s: &mut Cursive) -> Result<Metadata, String> {
+    match read_input(s, "save_parties") {
+        Some(path) if !path.trim().is_empty() => {
+            parse_metadata(path.trim()).map_err(|err| sentence_case(&err.to_string()))
+        }
+        _ => Ok(Metadata::default()),
+    }
+}
+
+/// Saves a chart of the results as SVG.
+pub fn save_chart_dialog(s: &mut Cursive, result: AllocationResult) {
+    let charts = SelectView::<Chart>::new()
+        .popup()
+        .item(t("Hemicycle"), Chart::Hemicycle)
+        .item(t("Votes and seats"), Chart::ShareBars)
+        .with_name("save_chart");
+
+    let options = LinearLayout::vertical()
+        .child(
+            LinearLayout::horizontal()
+                .child(TextView::new(format!("{} ", t("Chart:"))))
+                .child(charts),
+        )
+        .child(parties_input());
+
+    save_dialog_with(s, options, move |s, _| {
+        let chart = s
+            .call_on_name("save_chart", |view: &mut SelectView<Chart>| {
+                view.selection().map(|chart| *chart)
+            })
+            .flatten()
+            .unwrap_or(Chart::Hemicycle);
+        let metadata = read_metadata(s)?;
+
+        Ok(match chart {
+            Chart::Hemicycle => hemicycle(&result, &metadata),
+            Chart::ShareBars => share_bars(&result, &metadata),
+        })
+    });
+}
+
+#[derive(Clone, Copy)]
+enum Chart {
+    Hemicycle,
+    ShareBars,
+}
+
+/// Saves the seats, options and votes in the main window as an election
+/// file.
+pub fn save_election(s: &mut Cursive) {
+    let seat_count = read_input(s, "seat_count").and_then(|c| c.trim().parse().ok());
+    let options = s.user_data::<Options>().copied().unwrap_or_default();
+
+    let results = s
+        .call_on_name(
+            "votes_table",
+            |table: &mut TableView<PartyResults, PartyResultsColumn>| table.borrow_items().to_vec(),
+        )
+        .unwrap();
+
+    save_dialog(s, generate_project(seat_count, &options, &results));
+}
@@ -1,4 +1,24 @@
-use cursive::{views::EditView, Cursive};
+use cursive::{
+    align::HAlign,
+    event::{Event, Key},
+    view::{Nameable, Resizable},
+    views::{Dialog, EditView, LinearLayout, OnEventView, PaddedView, TextView},
+    Cursive,
+};
+use cursive_table_view::TableView;
+
+use crate::{csv_parser::ImportOptions, dhondt::Options, election::PartyResults, i18n::t};
+
+use dialogs::{
+    add::new_party_dialog,
+    columns::column_mapping_dialog,
+    confirm::{confirm_clear, confirm_quit},
+    edit::edit_party_dialog,
+    msgbox,
+    results::{show_report, start_calculation},
+    save::save_election,
+};
+use table_columns::PartyResultsColumn;
 
 pub mod dialogs;
 pub mod table_columns;
@@ -9,3 +29,148 @@ pub fn read_input(s: &mut Cursive, name: &str) -> Option<String> {
 
     Some(value.to_string())
 }
+
+/// Translated labels of fields shown one above the other, padded to the
+/// same width.
+pub fn field_labels<const N: usize>(labels: [&str; N]) -> [String; N] {
+    let labels = labels.map(t);
+    let width = labels
+        .iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or_default()
+        + 1;
+
+    labels.map(|label| format!("{label:<width$}"))
+}
+
+/// Capitalizes the first letter of a message, so that errors meant for the
+/// CLI can be shown in dialogs.
+pub fn sentence_case(message: &str) -> String {
+    let mut chars = message.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Runs the main window, where results can be entered and calculated.
+/// Warnings (e.g. from tallying the initial data) are shown on startup, and
+/// `pending_import` is a file whose columns have to be picked before loading.
+pub fn run(
+    initial_data: Vec<PartyResults>,
+    seat_count: Option<u32>,
+    options: Options,
+    pending_import: Option<(String, ImportOptions)>,
+    warnings: &[String],
+) {
+    let mut s = cursive::default();
+    s.set_user_data(options);
+    let mut table = TableView::<PartyResults, PartyResultsColumn>::new()
+        .column(PartyResultsColumn::Party, t("Party"), |c| c)
+        .column(PartyResultsColumn::Name, t("Name"), |c| c)
+        .column(PartyResultsColumn::Votes, t("Votes"), |c| {
+            c.width(11).align(HAlign::Right)
+        });
+
+    table.set_on_submit(edit_party_dialog);
+
+    s.add_layer(
+        OnEventView::new(
+            Dialog::around(
+                LinearLayout::vertical()
+                    .child(
+                        LinearLayout::horizontal()
+                            .child(TextView::new(format!("{} ", t("Seats to assign:"))))
+                            .child(EditView::new().with_name("seat_count").fixed_width(5)),
+                    )
+                    .child(PaddedView::lrtb(
+                        0,
+                        0,
+                        1,
+                        0,
+                        table.with_name("votes_table").min_size((75, 20)),
+                    )),
+            )
+            .title(t("Party results"))
+            .button(t("Add candidate"), new_party_dialog)
+            .button(t("Calculate"), start_calculation)
+            .button(t("Save election"), save_election)
+            .button(t("Clear"), confirm_clear),
+        )
+        .on_event(Key::Esc, confirm_quit)
+        .on_event(Event::CtrlChar('s'), start_calculation),
+    );
+
+    if !initial_data.is_empty() {
+        s.call_on_name(
+            "votes_table",
+            move |table: &mut TableView<PartyResults, PartyResultsColumn>| {
+                table.set_items(initial_data);
+            },
+        )
+        .unwrap();
+    }
+
+    if let Some(val) = seat_count {
+        s.call_on_name("seat_count", |view: &mut EditView| {
+            view.set_content(val.to_string());
+        });
+    }
+
+    if !warnings.is_empty() {
+        let warnings: Vec<String> = warnings.iter().map(|w| format!("- {w}")).collect();
+
+        msgbox(
+            &mut s,
+            t("Warnings"),
+            &format!(
+                "{}\n\n{}",
+                t("The following problems were found in the data:"),
+                warnings.join("\n")
+            ),
+        );
+    }
+
+    if let Some((path, import)) = pending_import {
+        column_mapping_dialog(&mut s, path, import);
+    }
+
+    s.run();
+}
+
+/// Runs a window that only shows a finished report, such as an STV count
+/// sheet.
+pub fn run_report(title: &str, report: String) {
+    let mut s = cursive::default();
+    let title = title.to_string();
+
+    s.add_layer(
+        Dialog::text(t("The count has finished."))
+            .title(title.clone())
+            .button(t("Show report"), {
+                let report = report.clone();
+                let title = title.clone();
+
+                move |s| show_report(s, &title, report.clone())
+            })
+            .button(t("Quit"), |s| s.quit()),
+    );
+    show_report(&mut s, &title, report);
+
+    s.run();
+}
+
+/// Runs a window showing the results of a partial count as it progresses.
+pub fn run_live(
+    seat_count: u32,
+    tally_path: String,
+    stations: Vec<(String, u32)>,
+    options: Options,
+) {
+    let mut s = cursive::default();
+    dialogs::live::show(&mut s, seat_count, tally_path, stations, options);
+
+    s.run();
+}
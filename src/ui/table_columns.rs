@@ -1,7 +1,7 @@
 use cursive_table_view::TableViewItem;
 use std::cmp::Ordering;
 
-use crate::numeric::format_num;
+use crate::{election::PartyResults, numeric::format_num};
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub enum PartyResultsColumn {
@@ -10,13 +10,6 @@ pub enum PartyResultsColumn {
     Votes,
 }
 
-#[derive(Clone, Debug, serde::Deserialize)]
-pub struct PartyResults {
-    pub party: String,
-    pub name: String,
-    pub votes: u32,
-}
-
 impl TableViewItem<PartyResultsColumn> for PartyResults {
     fn to_column(&self, column: PartyResultsColumn) -> String {
         match column {
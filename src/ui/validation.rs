@@ -1,6 +1,8 @@
 use cursive::Cursive;
 use str;
 
+use crate::i18n::{locale, t};
+
 use super::dialogs::error_msgbox;
 
 pub enum IntValidationError {
@@ -9,16 +11,17 @@ pub enum IntValidationError {
 }
 
 pub fn validate_number(s: &mut Cursive, number: &str) -> Result<u32, IntValidationError> {
-    let unformatted_number = str::replace(number, " ", "");
+    let (thousands, _) = locale().separators();
+    let unformatted_number = str::replace(number, " ", "").replace(thousands, "");
 
     match unformatted_number.parse::<u32>() {
         Ok(val) if val > 0 => Ok(val),
         Ok(_) => {
-            error_msgbox(s, "The number must be a positive integer");
+            error_msgbox(s, t("The number must be a positive integer"));
             Err(IntValidationError::ZeroValue)
         }
         Err(_) => {
-            error_msgbox(s, "Please provide a valid number of votes");
+            error_msgbox(s, t("Please provide a valid number of votes"));
             Err(IntValidationError::InvalidNumber)
         }
     }
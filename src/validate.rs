@@ -0,0 +1,266 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    dhondt::PartyCandidate,
+    election::PartyResults,
+    i18n::{t, t_with},
+    numeric::format_num,
+    text_table::format_table,
+};
+
+pub enum Issue {
+    Duplicate {
+        line: u64,
+        first_line: u64,
+        party: String,
+        name: String,
+    },
+    EmptyParty {
+        line: u64,
+    },
+    EmptyName {
+        line: u64,
+        party: String,
+    },
+    ZeroVotes {
+        line: u64,
+        party: String,
+        name: String,
+    },
+    /// A party name that only differs from an earlier one in whitespace or
+    /// case
+    PartyVariant {
+        line: u64,
+        party: String,
+        first_line: u64,
+        first_party: String,
+    },
+}
+
+impl Issue {
+    /// Whether the data can't be used as is. Other issues are only
+    /// suspicious.
+    pub fn is_error(&self) -> bool {
+        matches!(self, Issue::Duplicate { .. } | Issue::EmptyParty { .. })
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Issue::Duplicate {
+                line,
+                first_line,
+                party,
+                name,
+            } => f.write_str(&t_with(
+                "line {line}: {candidate} was already given on line {first_line}",
+                &[
+                    ("line", line),
+                    ("candidate", &describe(party, name)),
+                    ("first_line", first_line),
+                ],
+            )),
+            Issue::EmptyParty { line } => f.write_str(&t_with(
+                "line {line}: the party name is empty",
+                &[("line", line)],
+            )),
+            Issue::EmptyName { line, party } => f.write_str(&t_with(
+                "line {line}: a candidate of {party} has no name",
+                &[("line", line), ("party", party)],
+            )),
+            Issue::ZeroVotes { line, party, name } => f.write_str(&t_with(
+                "line {line}: {candidate} has no votes",
+                &[("line", line), ("candidate", &describe(party, name))],
+            )),
+            Issue::PartyVariant {
+                line,
+                party,
+                first_line,
+                first_party,
+            } => f.write_str(&t_with(
+                "line {line}: party '{party}' looks like '{first_party}' (line {first_line})",
+                &[
+                    ("line", line),
+                    ("party", party),
+                    ("first_party", first_party),
+                    ("first_line", first_line),
+                ],
+            )),
+        }
+    }
+}
+
+pub(crate) fn describe(party: &str, name: &str) -> String {
+    if name.is_empty() {
+        party.to_string()
+    } else {
+        format!("{name} ({party})")
+    }
+}
+
+/// Party name with runs of whitespace collapsed and in lowercase, to spot
+/// variants of the same party.
+fn normalize(party: &str) -> String {
+    party
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+pub struct Validation {
+    pub issues: Vec<Issue>,
+    pub rows: usize,
+    /// Votes of every party, in order of appearance
+    pub votes_by_party: Vec<(String, u32)>,
+    pub total_votes: u32,
+}
+
+/// Checks rows of results (with the line they were read at) for problems
+/// that would silently change the outcome.
+pub fn validate(rows: &[(u64, PartyResults)]) -> Validation {
+    let mut issues = Vec::new();
+    let mut first_lines: HashMap<PartyCandidate, u64> = HashMap::new();
+    let mut party_spellings: HashMap<String, (u64, String)> = HashMap::new();
+    let mut votes_by_party: Vec<(String, u32)> = Vec::new();
+
+    // Files with party totals only have no names at all
+    let has_candidates = rows.iter().any(|(_, r)| !r.name.is_empty());
+
+    for (line, row) in rows {
+        let line = *line;
+
+        if row.party.is_empty() {
+            issues.push(Issue::EmptyParty { line });
+        }
+
+        if has_candidates && row.name.is_empty() {
+            issues.push(Issue::EmptyName {
+                line,
+                party: row.party.clone(),
+            });
+        }
+
+        if row.votes == 0 {
+            issues.push(Issue::ZeroVotes {
+                line,
+                party: row.party.clone(),
+                name: row.name.clone(),
+            });
+        }
+
+        match first_lines.get(&(row.party.clone(), row.name.clone())) {
+            Some(&first_line) => issues.push(Issue::Duplicate {
+                line,
+                first_line,
+                party: row.party.clone(),
+                name: row.name.clone(),
+            }),
+            None => {
+                first_lines.insert((row.party.clone(), row.name.clone()), line);
+            }
+        }
+
+        match party_spellings.get(&normalize(&row.party)) {
+            Some((first_line, first_party)) if first_party != &row.party => {
+                issues.push(Issue::PartyVariant {
+                    line,
+                    party: row.party.clone(),
+                    first_line: *first_line,
+                    first_party: first_party.clone(),
+                })
+            }
+            Some(_) => {}
+            None => {
+                party_spellings.insert(normalize(&row.party), (line, row.party.clone()));
+            }
+        }
+
+        match votes_by_party.iter_mut().find(|(p, _)| p == &row.party) {
+            Some((_, votes)) => *votes += row.votes,
+            None => votes_by_party.push((row.party.clone(), row.votes)),
+        }
+    }
+
+    Validation {
+        issues,
+        rows: rows.len(),
+        total_votes: rows.iter().map(|(_, r)| r.votes).sum(),
+        votes_by_party,
+    }
+}
+
+/// The first row for the same party and candidate as an earlier one.
+pub fn find_duplicate(rows: &[PartyResults]) -> Option<&PartyResults> {
+    let mut seen = HashSet::new();
+
+    rows.iter().find(|r| !seen.insert((&r.party, &r.name)))
+}
+
+/// Adds up the votes of rows for the same party and candidate, keeping the
+/// position of the first one.
+pub fn merge_duplicates(rows: &[PartyResults]) -> Vec<PartyResults> {
+    let mut merged: Vec<PartyResults> = Vec::new();
+    let mut indices: HashMap<PartyCandidate, usize> = HashMap::new();
+
+    for row in rows {
+        match indices.get(&(row.party.clone(), row.name.clone())) {
+            Some(&i) => merged[i].votes += row.votes,
+            None => {
+                indices.insert((row.party.clone(), row.name.clone()), merged.len());
+                merged.push(row.clone());
+            }
+        }
+    }
+
+    merged
+}
+
+pub fn generate_report(validation: &Validation) -> String {
+    let mut output = String::new();
+
+    let errors = validation.issues.iter().filter(|i| i.is_error()).count();
+    let warnings = validation.issues.len() - errors;
+
+    output.push_str(&t_with(
+        "{rows} rows checked: {errors} errors, {warnings} warnings",
+        &[
+            ("rows", &validation.rows),
+            ("errors", &errors),
+            ("warnings", &warnings),
+        ],
+    ));
+    output.push_str("\n\n");
+
+    for issue in &validation.issues {
+        let kind = if issue.is_error() {
+            t("error")
+        } else {
+            t("warning")
+        };
+        output.push_str(&format!("{kind}: {issue}\n"));
+    }
+
+    if !validation.issues.is_empty() {
+        output.push('\n');
+    }
+
+    let mut lines: Vec<Vec<String>> = validation
+        .votes_by_party
+        .iter()
+        .map(|(party, votes)| vec![format!("{party}:"), format_num(*votes)])
+        .collect();
+    lines.push(vec![]);
+    lines.push(vec![
+        t("Total of votes:").into(),
+        format_num(validation.total_votes),
+    ]);
+
+    output.push_str(&format_table(&lines, &[true, false]));
+
+    output
+}